@@ -0,0 +1,74 @@
+//! Audit log: who used which control command, when, and what happened.
+//! Every action is appended to a local file (`mcdcbot_audit_log`), and optionally posted to the
+//! report channel (`mcdcbot_audit_report=true`).
+
+use std::{fs::File, io::Write};
+
+use serenity::{model::id::ChannelId, model::user::User, prelude::Context};
+
+pub struct AuditLog {
+    path: String,
+    /// None if the file couldn't be opened, in which case actions are only printed
+    file: std::sync::Mutex<Option<File>>,
+    /// the report channel, if actions should also be posted there
+    report_channel_id: Option<u64>,
+}
+
+impl AuditLog {
+    /// `default_path` is used if `mcdcbot_audit_log` isn't set
+    pub fn from_env(default_path: String, report_channel_id: u64) -> Self {
+        let path = std::env::var("mcdcbot_audit_log").unwrap_or(default_path);
+        let file = match File::options().append(true).create(true).open(&path) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                eprintln!("Couldn't open audit log {path}: {e}");
+                None
+            }
+        };
+        let report = std::env::var("mcdcbot_audit_report").is_ok_and(|v| v.trim() == "true");
+        Self {
+            path,
+            file: std::sync::Mutex::new(file),
+            report_channel_id: report.then_some(report_channel_id),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// records that `user` did `action` (like `mc.run say hi`), with the outcome (like `sent`, or why it failed)
+    pub async fn record(&self, ctx: &Context, user: &User, action: &str, outcome: &str) {
+        // one line per action, even if the command contained line breaks
+        let action = action.replace('\n', "\\n");
+        let outcome = outcome.replace('\n', "\\n");
+        let line = format!(
+            "{} {} ({}) {action}: {outcome}",
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            user.tag(),
+            user.id.0,
+        );
+        eprintln!("[audit] {line}");
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            if let Err(e) = writeln!(file, "{line}") {
+                eprintln!("Couldn't write to audit log {}: {e}", self.path);
+            }
+        }
+        if let Some(report_channel_id) = self.report_channel_id {
+            if let Err(e) = ChannelId(report_channel_id)
+                .send_message(&ctx.http, |m| {
+                    m.content(format!(
+                        "<@{}> `{}`: {outcome}",
+                        user.id.0,
+                        action.replace('`', "'")
+                    ))
+                    .allowed_mentions(|m| m.empty_parse())
+                })
+                .await
+            {
+                eprintln!("Error sending message: {:?}", e);
+                crate::metrics::discord_send_error();
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Confirmation for disruptive actions (stopping or restarting the server, restarting the bot)
+//! while players are online. Like whitelist requests, everything is stored
+//! in the buttons' custom ids.
+
+use serenity::{builder::CreateComponents, model::application::component::ButtonStyle};
+
+/// how long the confirm button works, in seconds
+pub const CONFIRM_TIMEOUT: i64 = 60;
+
+pub struct Confirmation {
+    /// unix timestamp of when confirmation was requested
+    pub issued: i64,
+    /// only the user who used the command can confirm it
+    pub user_id: u64,
+    /// `stop`, `restart` or `restart-bot`
+    pub action: String,
+}
+
+pub enum ConfirmButton {
+    Confirm(Confirmation),
+    Cancel { user_id: u64 },
+}
+
+impl Confirmation {
+    /// `confirm:<issued>:<user id>:<action>`, None if it would be too long for discord (100 characters)
+    pub fn custom_id(&self) -> Option<String> {
+        let id = format!("confirm:{}:{}:{}", self.issued, self.user_id, self.action);
+        (id.len() <= 100).then_some(id)
+    }
+    pub fn is_expired(&self, now: i64) -> bool {
+        now - self.issued > CONFIRM_TIMEOUT
+    }
+}
+
+impl ConfirmButton {
+    pub fn parse(custom_id: &str) -> Option<Self> {
+        if let Some(user_id) = custom_id.strip_prefix("confirm-cancel:") {
+            return Some(Self::Cancel {
+                user_id: user_id.parse().ok()?,
+            });
+        }
+        let mut parts = custom_id.strip_prefix("confirm:")?.splitn(3, ':');
+        Some(Self::Confirm(Confirmation {
+            issued: parts.next()?.parse().ok()?,
+            user_id: parts.next()?.parse().ok()?,
+            action: parts.next()?.to_owned(),
+        }))
+    }
+}
+
+/// the confirm and cancel buttons. `confirm_id` is from `Confirmation::custom_id`.
+pub fn buttons<'a>(
+    c: &'a mut CreateComponents,
+    confirm_id: &str,
+    user_id: u64,
+) -> &'a mut CreateComponents {
+    c.create_action_row(|row| {
+        row.create_button(|b| {
+            b.custom_id(confirm_id)
+                .label("Confirm")
+                .style(ButtonStyle::Danger)
+        })
+        .create_button(|b| {
+            b.custom_id(format!("confirm-cancel:{user_id}"))
+                .label("Cancel")
+                .style(ButtonStyle::Secondary)
+        })
+    })
+}
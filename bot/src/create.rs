@@ -0,0 +1,216 @@
+//! Creating new servers from templates (mc.create).
+//!
+//! A template is a directory inside `mcdcbot_templates` containing everything a new server needs
+//! (jar, server.properties, datapacks, ...) and a `mcdcbot_template.txt` file with the config lines
+//! for the servers file (like `type=...`, `exec=...`, `ram=...`, but without `dir=`).
+//! New servers are created in `mcdcbot_create_dir/<id>`.
+
+use std::{fmt::Display, io, path::Path};
+
+use minecraft_manager::{MinecraftServerSettings, MinecraftServerSettingsFromLinesError};
+
+pub const TEMPLATE_CONFIG_FILE: &str = "mcdcbot_template.txt";
+
+#[derive(Default)]
+pub struct CreateOptions {
+    pub seed: Option<String>,
+    pub gamemode: Option<String>,
+    pub difficulty: Option<String>,
+    pub accept_eula: bool,
+}
+
+impl CreateOptions {
+    /// parses a `key=value` option. returns false if `arg` isn't an option.
+    pub fn parse_arg(&mut self, arg: &str) -> Result<bool, CreateError> {
+        let Some((key, value)) = arg.split_once('=') else {
+            return Ok(false);
+        };
+        match key {
+            "seed" => self.seed = Some(value.to_owned()),
+            "gamemode" => match value {
+                "survival" | "creative" | "adventure" | "spectator" => {
+                    self.gamemode = Some(value.to_owned())
+                }
+                _ => return Err(CreateError::InvalidOption(arg.to_owned())),
+            },
+            "difficulty" => match value {
+                "peaceful" | "easy" | "normal" | "hard" => self.difficulty = Some(value.to_owned()),
+                _ => return Err(CreateError::InvalidOption(arg.to_owned())),
+            },
+            "eula" => match value {
+                "true" | "accept" => self.accept_eula = true,
+                _ => return Err(CreateError::InvalidOption(arg.to_owned())),
+            },
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+/// copies the template to `create_dir/id` and applies the options.
+/// returns the config lines for the servers file and the parsed settings.
+pub fn create(
+    templates_dir: &str,
+    create_dir: &str,
+    id: &str,
+    template: &str,
+    options: &CreateOptions,
+) -> Result<(Vec<String>, MinecraftServerSettings), CreateError> {
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(CreateError::InvalidId(id.to_owned()));
+    }
+    if template.is_empty() || template.contains('/') || template.starts_with('.') {
+        return Err(CreateError::NoSuchTemplate(template.to_owned()));
+    }
+    let template_dir = Path::new(templates_dir).join(template);
+    let template_config = match std::fs::read_to_string(template_dir.join(TEMPLATE_CONFIG_FILE)) {
+        Ok(v) => v,
+        Err(_) => return Err(CreateError::NoSuchTemplate(template.to_owned())),
+    };
+    let target_dir = Path::new(create_dir).join(id);
+    if target_dir.exists() {
+        return Err(CreateError::DirectoryExists(
+            target_dir.to_string_lossy().into_owned(),
+        ));
+    }
+    let mut lines = vec![format!("dir={}", target_dir.to_string_lossy())];
+    lines.extend(
+        template_config
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with("dir="))
+            .map(|line| line.to_owned()),
+    );
+    // check the config before copying anything
+    let settings = MinecraftServerSettings::from_lines(&mut lines.iter().map(|v| v.as_str()))
+        .map_err(CreateError::InvalidTemplateConfig)?;
+
+    // everything is done in a temporary directory first, so a failed copy doesn't leave a
+    // half-created server behind, which would make every retry fail because the directory exists
+    let tmp_dir = Path::new(create_dir).join(format!(".{id}.part"));
+    if tmp_dir.exists() {
+        // left over from a previous attempt which was interrupted
+        std::fs::remove_dir_all(&tmp_dir)?;
+    }
+    if let Err(e) = prepare(&template_dir, &tmp_dir, &settings, options) {
+        _ = std::fs::remove_dir_all(&tmp_dir);
+        return Err(e);
+    }
+    if let Err(e) = std::fs::rename(&tmp_dir, &target_dir) {
+        _ = std::fs::remove_dir_all(&tmp_dir);
+        return Err(e.into());
+    }
+    Ok((lines, settings))
+}
+
+/// copies the template to `dir` and applies the options
+fn prepare(
+    template_dir: &Path,
+    dir: &Path,
+    settings: &MinecraftServerSettings,
+    options: &CreateOptions,
+) -> Result<(), CreateError> {
+    copy_dir(template_dir, dir)?;
+    let mut properties = vec![];
+    if let Some(seed) = &options.seed {
+        properties.push(("level-seed", seed.as_str()));
+    }
+    if let Some(gamemode) = &options.gamemode {
+        properties.push(("gamemode", gamemode.as_str()));
+    }
+    if let Some(difficulty) = &options.difficulty {
+        properties.push(("difficulty", difficulty.as_str()));
+    }
+    if !properties.is_empty() {
+        set_properties(&dir.join("server.properties"), &properties)?;
+    }
+    if options.accept_eula {
+        MinecraftServerSettings {
+            directory: dir.to_string_lossy().into_owned(),
+            ..settings.clone()
+        }
+        .accept_eula()?;
+    }
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_name() == TEMPLATE_CONFIG_FILE {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            std::fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// sets keys in a server.properties file, keeping all other lines. creates the file if it doesn't exist.
+fn set_properties(path: &Path, properties: &[(&str, &str)]) -> io::Result<()> {
+    let file = match std::fs::read_to_string(path) {
+        Ok(v) => v,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let mut out = String::new();
+    let mut done = vec![false; properties.len()];
+    for line in file.lines() {
+        let key = line.split_once('=').map(|v| v.0.trim());
+        if let Some(i) = properties.iter().position(|(k, _)| Some(*k) == key) {
+            out.push_str(&format!("{}={}\n", properties[i].0, properties[i].1));
+            done[i] = true;
+        } else {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    for (i, (key, value)) in properties.iter().enumerate() {
+        if !done[i] {
+            out.push_str(&format!("{key}={value}\n"));
+        }
+    }
+    std::fs::write(path, out)
+}
+
+#[derive(Debug)]
+pub enum CreateError {
+    InvalidId(String),
+    InvalidOption(String),
+    NoSuchTemplate(String),
+    DirectoryExists(String),
+    InvalidTemplateConfig(MinecraftServerSettingsFromLinesError),
+    Io(io::Error),
+}
+impl Display for CreateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidId(id) => write!(
+                f,
+                "invalid id '{id}' (use letters, numbers, '-' and '_' only)"
+            ),
+            Self::InvalidOption(o) => write!(f, "invalid option '{o}'"),
+            Self::NoSuchTemplate(t) => write!(f, "template '{t}' doesn't exist"),
+            Self::DirectoryExists(d) => write!(f, "directory {d} already exists"),
+            Self::InvalidTemplateConfig(e) => {
+                write!(
+                    f,
+                    "{TEMPLATE_CONFIG_FILE} of this template is invalid: {e:?}"
+                )
+            }
+            Self::Io(e) => write!(f, "couldn't copy template: {e}"),
+        }
+    }
+}
+impl From<io::Error> for CreateError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
@@ -0,0 +1,248 @@
+//! The bot's sqlite database (`mcdcbot_db`), which stores player sessions and linked accounts.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// sessions of the player `?1`, including those where the player used a different name
+/// (if the uuid is known)
+const PLAYER_SESSIONS: &str = "(player = ?1 COLLATE NOCASE
+    OR uuid IN (SELECT uuid FROM sessions WHERE player = ?1 COLLATE NOCASE))";
+
+pub struct Db {
+    conn: Connection,
+}
+
+/// total playtime of a player in one mode
+pub struct Playtime {
+    pub mode: String,
+    pub seconds: i64,
+}
+
+pub enum Seen {
+    /// (mode, joined) of the open session
+    Online(String, i64),
+    /// (mode, left) of the most recent session
+    Offline(String, i64),
+    Never,
+}
+
+impl Db {
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                id INTEGER PRIMARY KEY,
+                mode TEXT NOT NULL,
+                player TEXT NOT NULL,
+                joined INTEGER NOT NULL,
+                left INTEGER,
+                uuid TEXT
+            );
+            CREATE INDEX IF NOT EXISTS sessions_player ON sessions (player COLLATE NOCASE);
+            CREATE TABLE IF NOT EXISTS links (
+                discord_id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                uuid TEXT,
+                linked INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS heartbeat (
+                id INTEGER PRIMARY KEY CHECK (id = 0),
+                time INTEGER NOT NULL
+            );",
+        )?;
+        // databases created before uuids were tracked
+        if conn.prepare("SELECT uuid FROM sessions").is_err() {
+            conn.execute("ALTER TABLE sessions ADD COLUMN uuid TEXT", params![])?;
+        }
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS sessions_uuid ON sessions (uuid)",
+            params![],
+        )?;
+        // sessions which are still open were interrupted by the bot exiting or crashing,
+        // so they end at the last heartbeat, which is at most a minute before that.
+        conn.execute(
+            "UPDATE sessions SET left = MAX(joined, COALESCE((SELECT time FROM heartbeat), joined))
+            WHERE left IS NULL",
+            params![],
+        )?;
+        Ok(Self { conn })
+    }
+
+    pub fn player_joined(
+        &self,
+        mode: &str,
+        player: &str,
+        uuid: Option<&str>,
+        time: i64,
+    ) -> rusqlite::Result<()> {
+        // if we missed the leave message, end the old session now
+        self.player_left(mode, player, time)?;
+        if let Some(uuid) = uuid {
+            // sessions from before the uuid was known
+            self.conn.execute(
+                "UPDATE sessions SET uuid = ?2 WHERE player = ?1 AND uuid IS NULL",
+                params![player, uuid],
+            )?;
+            // the player may have changed their name
+            self.conn.execute(
+                "UPDATE links SET name = ?1 WHERE uuid = ?2",
+                params![player, uuid],
+            )?;
+        }
+        self.conn.execute(
+            "INSERT INTO sessions (mode, player, joined, uuid) VALUES (?1, ?2, ?3, ?4)",
+            params![mode, player, time, uuid],
+        )?;
+        Ok(())
+    }
+    pub fn player_left(&self, mode: &str, player: &str, time: i64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET left = ?3 WHERE mode = ?1 AND player = ?2 AND left IS NULL",
+            params![mode, player, time],
+        )?;
+        Ok(())
+    }
+    /// ends all sessions in this mode, used when the server stops
+    pub fn server_stopped(&self, mode: &str, time: i64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET left = ?2 WHERE mode = ?1 AND left IS NULL",
+            params![mode, time],
+        )?;
+        Ok(())
+    }
+
+    /// records that the server (and so the open sessions) was still running at `time`
+    pub fn heartbeat(&self, time: i64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO heartbeat (id, time) VALUES (0, ?1)",
+            params![time],
+        )?;
+        Ok(())
+    }
+
+    /// the player's total playtime per mode, most played first. open sessions count until `now`.
+    pub fn playtime(&self, player: &str, now: i64) -> rusqlite::Result<Vec<Playtime>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT mode, SUM(COALESCE(left, ?2) - joined) AS seconds FROM sessions
+            WHERE {PLAYER_SESSIONS} GROUP BY mode ORDER BY seconds DESC"
+        ))?;
+        let rows = stmt.query_map(params![player, now], |row| {
+            Ok(Playtime {
+                mode: row.get(0)?,
+                seconds: row.get(1)?,
+            })
+        })?;
+        rows.collect()
+    }
+    /// the `limit` players with the most playtime across all modes, by their most recent name
+    pub fn top_playtime(&self, limit: usize, now: i64) -> rusqlite::Result<Vec<(String, i64)>> {
+        // with MAX(), sqlite takes `player` from the row with the highest `joined`
+        let mut stmt = self.conn.prepare(
+            "SELECT player, MAX(joined), SUM(COALESCE(left, ?1) - joined) AS seconds FROM sessions
+            GROUP BY COALESCE(uuid, LOWER(player)) ORDER BY seconds DESC LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![now, limit as i64], |row| {
+            Ok((row.get(0)?, row.get(2)?))
+        })?;
+        rows.collect()
+    }
+    pub fn seen(&self, player: &str) -> rusqlite::Result<Seen> {
+        let online = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT mode, joined FROM sessions WHERE {PLAYER_SESSIONS} AND left IS NULL
+                    ORDER BY joined DESC LIMIT 1"
+                ),
+                params![player],
+                |row| Ok(Seen::Online(row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        if let Some(online) = online {
+            return Ok(online);
+        }
+        Ok(self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT mode, left FROM sessions WHERE {PLAYER_SESSIONS}
+                    ORDER BY left DESC LIMIT 1"
+                ),
+                params![player],
+                |row| Ok(Seen::Offline(row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .unwrap_or(Seen::Never))
+    }
+
+    /// links the discord user to the minecraft account, replacing any old links of either of them
+    pub fn link(
+        &self,
+        discord_id: u64,
+        name: &str,
+        uuid: Option<&str>,
+        time: i64,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "DELETE FROM links WHERE discord_id = ?1 OR name = ?2 COLLATE NOCASE OR uuid = ?3",
+            params![discord_id as i64, name, uuid],
+        )?;
+        self.conn.execute(
+            "INSERT INTO links (discord_id, name, uuid, linked) VALUES (?1, ?2, ?3, ?4)",
+            params![discord_id as i64, name, uuid, time],
+        )?;
+        Ok(())
+    }
+    /// returns false if the user wasn't linked
+    pub fn unlink(&self, discord_id: u64) -> rusqlite::Result<bool> {
+        Ok(self.conn.execute(
+            "DELETE FROM links WHERE discord_id = ?1",
+            params![discord_id as i64],
+        )? > 0)
+    }
+    /// the (name, uuid) of the minecraft account linked to the discord user
+    pub fn linked_account(
+        &self,
+        discord_id: u64,
+    ) -> rusqlite::Result<Option<(String, Option<String>)>> {
+        self.conn
+            .query_row(
+                "SELECT name, uuid FROM links WHERE discord_id = ?1",
+                params![discord_id as i64],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+    }
+    /// the discord user linked to the minecraft account with this name (or a previous name of it)
+    pub fn linked_discord_id(&self, name: &str) -> rusqlite::Result<Option<u64>> {
+        self.conn
+            .query_row(
+                "SELECT discord_id FROM links WHERE name = ?1 COLLATE NOCASE
+                OR uuid IN (SELECT uuid FROM sessions WHERE player = ?1 COLLATE NOCASE)
+                LIMIT 1",
+                params![name],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()
+            .map(|id| id.map(|id| id as u64))
+    }
+}
+
+/// the current time as a unix timestamp, which is how times are stored in the database
+pub fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// `3d 4h`, `2h 15min`, `5min`
+pub fn format_duration(seconds: i64) -> String {
+    let minutes = seconds.max(0) / 60;
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}min")
+    } else {
+        format!("{minutes}min")
+    }
+}
@@ -0,0 +1,97 @@
+//! Making minecraft chat safe to post on discord: minecraft color codes are removed, markdown is
+//! escaped and `@everyone`/`@here` are defused. `@name` can become a real ping for allowed players.
+
+use std::collections::HashMap;
+
+pub struct DiscordText {
+    pub text: String,
+    /// users who are mentioned in `text` and should be pinged
+    pub pings: Vec<u64>,
+}
+
+/// `mcdcbot_chat_pings`: `name=discord user id` pairs, separated by commas.
+/// players can ping these users by writing `@name` in minecraft chat.
+pub fn pings_from_env() -> HashMap<String, u64> {
+    let Ok(pings) = std::env::var("mcdcbot_chat_pings") else {
+        return HashMap::new();
+    };
+    pings
+        .split(',')
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| {
+            let (name, id) = v.split_once('=').expect(
+                "mcdcbot_chat_pings env var must look like name=discord user id,name2=id2,...",
+            );
+            (
+                name.trim().to_lowercase(),
+                id.trim()
+                    .parse()
+                    .expect("mcdcbot_chat_pings env var contains an invalid user id!"),
+            )
+        })
+        .collect()
+}
+
+/// converts a minecraft chat message. `ping` returns the discord user id for a name after `@`,
+/// if that user may be pinged.
+pub fn from_minecraft(message: &str, ping: impl Fn(&str) -> Option<u64>) -> DiscordText {
+    let message = strip_color_codes(message);
+    let mut text = String::with_capacity(message.len());
+    let mut pings = vec![];
+    let mut chars = message.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '@' => {
+                // minecraft names are 3-16 characters from a-z, 0-9 and _
+                let name_len = message[i + 1..]
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+                    .count();
+                let name = &message[i + 1..i + 1 + name_len];
+                if let Some(id) = ping(name).filter(|_| !name.is_empty()) {
+                    text.push_str(&format!("<@{id}>"));
+                    if !pings.contains(&id) {
+                        pings.push(id);
+                    }
+                    while chars.next_if(|(j, _)| *j <= i + name_len).is_some() {}
+                } else {
+                    // a zero-width space keeps `@everyone` and `@here` from being mentions
+                    text.push_str("@\u{200B}");
+                }
+            }
+            'h' if message[i..].starts_with("https://") || message[i..].starts_with("http://") => {
+                // discord doesn't format links, and escaping them would change them
+                let len = message[i..]
+                    .find(char::is_whitespace)
+                    .unwrap_or(message.len() - i);
+                text.push_str(&message[i..i + len]);
+                while chars.next_if(|(j, _)| *j < i + len).is_some() {}
+            }
+            '\\' | '*' | '_' | '~' | '`' | '|' | '<' | '[' => {
+                text.push('\\');
+                text.push(ch);
+            }
+            // headings, lists and quotes
+            '#' | '-' | '>' if i == 0 => {
+                text.push('\\');
+                text.push(ch);
+            }
+            ch => text.push(ch),
+        }
+    }
+    DiscordText { text, pings }
+}
+
+/// removes minecraft's `§x` formatting codes, which some plugins put in chat messages
+fn strip_color_codes(text: &str) -> String {
+    let mut o = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '§' {
+            chars.next();
+        } else {
+            o.push(ch);
+        }
+    }
+    o
+}
@@ -0,0 +1,259 @@
+//! Downloading server jars (mc.install).
+//!
+//! Both endpoints can be changed through env variables, for example to test against a local server:
+//! - `mcdcbot_install_papermc_api` (default: https://api.papermc.io), a PaperMC-style download api (v2)
+//! - `mcdcbot_install_mojang_manifest` (default: https://piston-meta.mojang.com/mc/game/version_manifest_v2.json)
+
+use std::fmt::Display;
+
+use minecraft_manager::MinecraftServerType;
+use serde_json::Value;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+pub struct InstallApis {
+    pub papermc_api: String,
+    pub mojang_manifest: String,
+}
+
+impl InstallApis {
+    pub fn from_env() -> Self {
+        Self {
+            papermc_api: std::env::var("mcdcbot_install_papermc_api")
+                .unwrap_or_else(|_| "https://api.papermc.io".to_owned())
+                .trim_end_matches('/')
+                .to_owned(),
+            mojang_manifest: std::env::var("mcdcbot_install_mojang_manifest").unwrap_or_else(
+                |_| "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json".to_owned(),
+            ),
+        }
+    }
+
+    /// downloads the server jar for `project` (`vanilla`, or a papermc project like `paper`, `folia`, `velocity`)
+    /// in the given version (or `latest`) to `directory`, after checking its hash.
+    /// returns the file name of the new jar.
+    pub async fn install(
+        &self,
+        project: &str,
+        version: &str,
+        directory: &str,
+    ) -> Result<String, InstallError> {
+        // the version is put into api urls
+        if !is_valid_version(version) {
+            return Err(InstallError::InvalidVersion(version.to_owned()));
+        }
+        let client = reqwest::Client::new();
+        let (name, url, hash) = match project {
+            "vanilla" | "mojang" => self.find_mojang(&client, version).await?,
+            "paper" | "folia" | "velocity" | "waterfall" => {
+                self.find_papermc(&client, project, version).await?
+            }
+            _ => return Err(InstallError::UnknownProject(project.to_owned())),
+        };
+        // the name comes from the api and is used as a path, so it must not point anywhere else
+        if !is_valid_jar_name(&name) {
+            return Err(InstallError::InvalidFileName(name));
+        }
+        let bytes = client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let actual = match &hash {
+            Hash::Sha256(_) => format!("{:x}", Sha256::digest(&bytes)),
+            Hash::Sha1(_) => format!("{:x}", Sha1::digest(&bytes)),
+        };
+        let (Hash::Sha256(expected) | Hash::Sha1(expected)) = hash;
+        if !actual.eq_ignore_ascii_case(&expected) {
+            return Err(InstallError::ChecksumMismatch { expected, actual });
+        }
+        // write to a temporary file first so a failed write can't leave a broken jar behind
+        let path = format!("{directory}/{name}");
+        let tmp_path = format!("{path}.part");
+        std::fs::write(&tmp_path, &bytes)?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(name)
+    }
+
+    async fn find_papermc(
+        &self,
+        client: &reqwest::Client,
+        project: &str,
+        version: &str,
+    ) -> Result<(String, String, Hash), InstallError> {
+        let base = format!("{}/v2/projects/{project}", self.papermc_api);
+        let version = if version == "latest" {
+            let project_info = get_json(client, &base).await?;
+            let version = project_info["versions"]
+                .as_array()
+                .and_then(|v| v.last())
+                .and_then(|v| v.as_str())
+                .ok_or(InstallError::MissingField("versions"))?
+                .to_owned();
+            if !is_valid_version(&version) {
+                return Err(InstallError::InvalidVersion(version));
+            }
+            version
+        } else {
+            version.to_owned()
+        };
+        let builds = get_json(client, &format!("{base}/versions/{version}/builds")).await?;
+        let build = builds["builds"]
+            .as_array()
+            .and_then(|v| v.last())
+            .ok_or_else(|| InstallError::UnknownVersion(version.clone()))?;
+        let build_number = build["build"]
+            .as_u64()
+            .ok_or(InstallError::MissingField("build"))?;
+        let application = &build["downloads"]["application"];
+        let name = application["name"]
+            .as_str()
+            .ok_or(InstallError::MissingField("downloads.application.name"))?;
+        let sha256 = application["sha256"]
+            .as_str()
+            .ok_or(InstallError::MissingField("downloads.application.sha256"))?;
+        Ok((
+            name.to_owned(),
+            format!("{base}/versions/{version}/builds/{build_number}/downloads/{name}"),
+            Hash::Sha256(sha256.to_owned()),
+        ))
+    }
+
+    async fn find_mojang(
+        &self,
+        client: &reqwest::Client,
+        version: &str,
+    ) -> Result<(String, String, Hash), InstallError> {
+        let manifest = get_json(client, &self.mojang_manifest).await?;
+        let version = if version == "latest" {
+            manifest["latest"]["release"]
+                .as_str()
+                .ok_or(InstallError::MissingField("latest.release"))?
+        } else {
+            version
+        };
+        let version_url = manifest["versions"]
+            .as_array()
+            .and_then(|versions| versions.iter().find(|v| v["id"].as_str() == Some(version)))
+            .and_then(|v| v["url"].as_str())
+            .ok_or_else(|| InstallError::UnknownVersion(version.to_owned()))?;
+        let version_info = get_json(client, version_url).await?;
+        let server = &version_info["downloads"]["server"];
+        let url = server["url"]
+            .as_str()
+            .ok_or(InstallError::MissingField("downloads.server.url"))?;
+        // mojang only publishes sha1 hashes
+        let sha1 = server["sha1"]
+            .as_str()
+            .ok_or(InstallError::MissingField("downloads.server.sha1"))?;
+        Ok((
+            format!("minecraft_server.{version}.jar"),
+            url.to_owned(),
+            Hash::Sha1(sha1.to_owned()),
+        ))
+    }
+}
+
+/// the server type for jars of the project, and how it's written in the servers file
+pub fn server_type(project: &str) -> Option<(MinecraftServerType, &'static str)> {
+    Some(match project {
+        "vanilla" | "mojang" => (MinecraftServerType::VanillaMojang, "vanilla-mojang"),
+        "paper" => (MinecraftServerType::VanillaPaperMC, "vanilla-papermc"),
+        "folia" => (MinecraftServerType::Folia, "folia"),
+        "velocity" => (MinecraftServerType::Velocity, "velocity"),
+        "waterfall" => (MinecraftServerType::BungeeCord, "waterfall"),
+        _ => return None,
+    })
+}
+
+/// `1.20.4`, `1.21-pre1`, `24w14a`, ...
+fn is_valid_version(version: &str) -> bool {
+    !version.is_empty()
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+}
+
+/// a plain `*.jar` file name, without any directories
+fn is_valid_jar_name(name: &str) -> bool {
+    name.len() > ".jar".len()
+        && name.ends_with(".jar")
+        && !name.contains(['/', '\\'])
+        && !name.contains("..")
+}
+
+enum Hash {
+    Sha256(String),
+    Sha1(String),
+}
+
+async fn get_json(client: &reqwest::Client, url: &str) -> Result<Value, InstallError> {
+    let bytes = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+#[derive(Debug)]
+pub enum InstallError {
+    UnknownProject(String),
+    UnknownVersion(String),
+    InvalidVersion(String),
+    /// the api returned a file name which isn't a plain `*.jar` name
+    InvalidFileName(String),
+    MissingField(&'static str),
+    ChecksumMismatch {
+        expected: String,
+        actual: String,
+    },
+    Http(reqwest::Error),
+    Json(serde_json::Error),
+    Io(std::io::Error),
+}
+impl Display for InstallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownProject(p) => write!(
+                f,
+                "unknown server type '{p}', try vanilla, paper, folia, velocity or waterfall"
+            ),
+            Self::UnknownVersion(v) => write!(f, "version '{v}' not found"),
+            Self::InvalidVersion(v) => write!(
+                f,
+                "invalid version '{v}', versions can only contain letters, digits, '.' and '-'"
+            ),
+            Self::InvalidFileName(name) => {
+                write!(f, "api returned an invalid file name '{name}'")
+            }
+            Self::MissingField(field) => write!(f, "api response is missing '{field}'"),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch (expected {expected}, got {actual}), download discarded"
+            ),
+            Self::Http(e) => write!(f, "download failed: {e}"),
+            Self::Json(e) => write!(f, "invalid api response: {e}"),
+            Self::Io(e) => write!(f, "couldn't save jar: {e}"),
+        }
+    }
+}
+impl From<reqwest::Error> for InstallError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Http(e)
+    }
+}
+impl From<serde_json::Error> for InstallError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+impl From<std::io::Error> for InstallError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
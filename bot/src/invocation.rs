@@ -0,0 +1,200 @@
+//! Who used a control command and how to answer them, so text commands (`mc.stop`), slash
+//! commands (`/mc stop`) and the buttons on the status message can share the code which handles them.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serenity::{
+    builder::CreateComponents,
+    model::{
+        application::interaction::{
+            application_command::ApplicationCommandInteraction,
+            message_component::MessageComponentInteraction, InteractionResponseType,
+        },
+        channel::Message,
+        user::User,
+    },
+    prelude::Context,
+};
+
+pub struct Invocation<'a> {
+    pub user: &'a User,
+    /// the command as the user wrote it, for the audit log
+    pub action: String,
+    /// the user's roles, for permission checks
+    pub role_ids: Vec<u64>,
+    /// don't ask for confirmation, even if players are online (`--force`)
+    pub force: bool,
+    origin: Origin<'a>,
+    /// interactions have to be responded to once, further replies are followup messages
+    responded: AtomicBool,
+}
+
+enum Origin<'a> {
+    Message(&'a Message),
+    Command(&'a ApplicationCommandInteraction),
+    Component(&'a MessageComponentInteraction),
+}
+
+impl<'a> Invocation<'a> {
+    pub fn from_message(msg: &'a Message) -> Self {
+        Self {
+            user: &msg.author,
+            action: msg.content.clone(),
+            role_ids: match &msg.member {
+                Some(member) => member.roles.iter().map(|r| r.0).collect(),
+                None => vec![],
+            },
+            force: false,
+            origin: Origin::Message(msg),
+            responded: AtomicBool::new(false),
+        }
+    }
+    pub fn from_command(command: &'a ApplicationCommandInteraction, action: String) -> Self {
+        Self {
+            user: &command.user,
+            action,
+            role_ids: match &command.member {
+                Some(member) => member.roles.iter().map(|r| r.0).collect(),
+                None => vec![],
+            },
+            force: false,
+            origin: Origin::Command(command),
+            responded: AtomicBool::new(false),
+        }
+    }
+
+    pub fn from_component(component: &'a MessageComponentInteraction, action: String) -> Self {
+        Self {
+            user: &component.user,
+            action,
+            role_ids: match &component.member {
+                Some(member) => member.roles.iter().map(|r| r.0).collect(),
+                None => vec![],
+            },
+            force: false,
+            origin: Origin::Component(component),
+            responded: AtomicBool::new(false),
+        }
+    }
+
+    /// replies to the message, or responds to the interaction. returns the sent message,
+    /// which can be edited later (this is how the status message works).
+    pub async fn reply(&self, ctx: &Context, content: impl Into<String>) -> Option<Message> {
+        let content = content.into();
+        let sent = match self.origin {
+            Origin::Message(msg) => msg.reply(&ctx.http, content).await,
+            Origin::Command(command) => {
+                if self.responded.swap(true, Ordering::Relaxed) {
+                    command
+                        .create_followup_message(&ctx.http, |m| m.content(content))
+                        .await
+                } else {
+                    match command
+                        .create_interaction_response(&ctx.http, |r| {
+                            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                                .interaction_response_data(|d| d.content(content))
+                        })
+                        .await
+                    {
+                        Ok(()) => command.get_interaction_response(&ctx.http).await,
+                        Err(e) => Err(e),
+                    }
+                }
+            }
+            // a new message, the one with the button stays as it is
+            Origin::Component(component) => {
+                if self.responded.swap(true, Ordering::Relaxed) {
+                    component
+                        .create_followup_message(&ctx.http, |m| m.content(content))
+                        .await
+                } else {
+                    match component
+                        .create_interaction_response(&ctx.http, |r| {
+                            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                                .interaction_response_data(|d| d.content(content))
+                        })
+                        .await
+                    {
+                        Ok(()) => component.get_interaction_response(&ctx.http).await,
+                        Err(e) => Err(e),
+                    }
+                }
+            }
+        };
+        match sent {
+            Ok(message) => Some(message),
+            Err(e) => {
+                eprintln!("Error sending message: {:?}", e);
+                crate::metrics::discord_send_error();
+                None
+            }
+        }
+    }
+
+    /// like `reply`, but with buttons
+    pub async fn reply_with_components<F>(&self, ctx: &Context, content: String, components: F)
+    where
+        F: FnOnce(&mut CreateComponents) -> &mut CreateComponents,
+    {
+        let sent = match self.origin {
+            Origin::Message(msg) => msg
+                .channel_id
+                .send_message(&ctx.http, |m| {
+                    m.content(content)
+                        .reference_message(msg)
+                        .components(components)
+                })
+                .await
+                .map(|_| ()),
+            Origin::Command(command) => {
+                if self.responded.swap(true, Ordering::Relaxed) {
+                    command
+                        .create_followup_message(&ctx.http, |m| {
+                            m.content(content).components(components)
+                        })
+                        .await
+                        .map(|_| ())
+                } else {
+                    command
+                        .create_interaction_response(&ctx.http, |r| {
+                            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                                .interaction_response_data(|d| {
+                                    d.content(content).components(components)
+                                })
+                        })
+                        .await
+                }
+            }
+            Origin::Component(component) => {
+                if self.responded.swap(true, Ordering::Relaxed) {
+                    component
+                        .create_followup_message(&ctx.http, |m| {
+                            m.content(content).components(components)
+                        })
+                        .await
+                        .map(|_| ())
+                } else {
+                    component
+                        .create_interaction_response(&ctx.http, |r| {
+                            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                                .interaction_response_data(|d| {
+                                    d.content(content).components(components)
+                                })
+                        })
+                        .await
+                }
+            }
+        };
+        if let Err(e) = sent {
+            eprintln!("Error sending message: {:?}", e);
+            crate::metrics::discord_send_error();
+        }
+    }
+
+    /// interactions must be responded to, this says so if nothing else was sent
+    pub async fn finish(&self, ctx: &Context) {
+        if !matches!(self.origin, Origin::Message(_)) && !self.responded.load(Ordering::Relaxed) {
+            self.reply(ctx, "done.").await;
+        }
+    }
+}
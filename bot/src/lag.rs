@@ -0,0 +1,79 @@
+//! Deciding when lag is bad enough to post a warning to the status channel.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use minecraft_manager::events::{LagEvent, TpsReport};
+
+/// warn if there were this many `Can't keep up!` messages within `LAG_WINDOW`
+const LAG_EVENTS_FOR_WARNING: usize = 3;
+const LAG_WINDOW: Duration = Duration::from_secs(5 * 60);
+/// warn if the 1-minute tps was below `LOW_TPS` in this many consecutive reports (one per minute)
+const LOW_TPS: f64 = 15.0;
+const LOW_TPS_REPORTS_FOR_WARNING: u32 = 3;
+/// don't warn more often than this
+const WARNING_COOLDOWN: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Default)]
+pub struct LagTracker {
+    lag_events: VecDeque<Instant>,
+    low_tps_reports: u32,
+    last_warning: Option<Instant>,
+    /// the most recent tps report, for the status message
+    pub tps: Option<TpsReport>,
+}
+
+impl LagTracker {
+    /// returns a warning message if the server has been lagging for a while
+    pub fn on_lag(&mut self, lag: &LagEvent) -> Option<String> {
+        let now = Instant::now();
+        self.lag_events.push_back(now);
+        while self
+            .lag_events
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > LAG_WINDOW)
+        {
+            self.lag_events.pop_front();
+        }
+        if self.lag_events.len() >= LAG_EVENTS_FOR_WARNING {
+            self.warn(format!(
+                "The server couldn't keep up {} times in the last {} minutes (most recently {}ms or {} ticks behind). The host may be overloaded.",
+                self.lag_events.len(),
+                LAG_WINDOW.as_secs() / 60,
+                lag.ms_behind,
+                lag.ticks_behind,
+            ))
+        } else {
+            None
+        }
+    }
+    /// returns a warning message if the tps has been low for a while
+    pub fn on_tps(&mut self, tps: &TpsReport) -> Option<String> {
+        self.tps = Some(tps.clone());
+        if tps.one_minute < LOW_TPS {
+            self.low_tps_reports += 1;
+        } else {
+            self.low_tps_reports = 0;
+        }
+        if self.low_tps_reports >= LOW_TPS_REPORTS_FOR_WARNING {
+            self.warn(format!(
+                "The server has been running at less than {LOW_TPS} TPS for {} minutes (now {:.1}). The host may be overloaded.",
+                self.low_tps_reports, tps.one_minute,
+            ))
+        } else {
+            None
+        }
+    }
+    fn warn(&mut self, message: String) -> Option<String> {
+        if self
+            .last_warning
+            .is_some_and(|t| t.elapsed() < WARNING_COOLDOWN)
+        {
+            return None;
+        }
+        self.last_warning = Some(Instant::now());
+        Some(message)
+    }
+}
@@ -0,0 +1,100 @@
+//! Linking discord users to minecraft accounts (mc.link).
+//! The bot sends a one-time code to the player in minecraft, which they then confirm on discord.
+//! Links are stored in the database, see `Db::link`.
+
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    fmt::Display,
+    hash::{BuildHasher, Hasher},
+    time::{Duration, Instant},
+};
+
+/// how long a code can be confirmed
+pub const CODE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// links which have been requested, but not confirmed yet, by discord user id
+#[derive(Default)]
+pub struct PendingLinks {
+    pending: HashMap<u64, PendingLink>,
+}
+
+struct PendingLink {
+    name: String,
+    uuid: Option<String>,
+    code: String,
+    created: Instant,
+}
+
+impl PendingLinks {
+    /// starts linking the discord user to the player and returns the code, replacing any older code for this user
+    pub fn start(&mut self, discord_id: u64, name: String, uuid: Option<String>) -> String {
+        let code = generate_code();
+        self.pending.insert(
+            discord_id,
+            PendingLink {
+                name,
+                uuid,
+                code: code.clone(),
+                created: Instant::now(),
+            },
+        );
+        code
+    }
+    /// returns the (name, uuid) of the player if the code is correct.
+    /// the code can only be tried once, after that a new one has to be requested.
+    pub fn confirm(
+        &mut self,
+        discord_id: u64,
+        code: &str,
+    ) -> Result<(String, Option<String>), LinkError> {
+        let pending = self
+            .pending
+            .remove(&discord_id)
+            .ok_or(LinkError::NothingToConfirm)?;
+        if pending.created.elapsed() > CODE_TIMEOUT {
+            Err(LinkError::Expired)
+        } else if pending.code != code {
+            Err(LinkError::WrongCode)
+        } else {
+            Ok((pending.name, pending.uuid))
+        }
+    }
+}
+
+/// a random 6-digit code
+fn generate_code() -> String {
+    // RandomState is seeded randomly, which is good enough for codes that are only valid for a few minutes
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|t| t.as_nanos())
+            .unwrap_or(0),
+    );
+    format!("{:06}", hasher.finish() % 1_000_000)
+}
+
+#[derive(Debug)]
+pub enum LinkError {
+    NothingToConfirm,
+    Expired,
+    WrongCode,
+}
+impl Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NothingToConfirm => write!(
+                f,
+                "there is nothing to confirm, use `mc.link <minecraft name>` first"
+            ),
+            Self::Expired => write!(
+                f,
+                "the code has expired, use `mc.link <minecraft name>` to get a new one"
+            ),
+            Self::WrongCode => write!(
+                f,
+                "wrong code, use `mc.link <minecraft name>` to get a new one"
+            ),
+        }
+    }
+}
@@ -1,29 +1,85 @@
+mod audit;
+mod confirm;
+mod create;
+mod db;
+mod discord_text;
+mod install;
+mod invocation;
+mod lag;
+mod link;
+mod metrics;
+mod permissions;
+mod servers_file;
+mod slash;
+mod stats;
+mod status_buttons;
+mod tellraw;
+mod webhook;
+mod whitelist;
+
+use minecraft_manager::chat::TextComponent;
 use minecraft_manager::events::MinecraftServerEventType;
-use minecraft_manager::{self, tasks::MinecraftServerTask, MinecraftServerSettings};
+use minecraft_manager::resources::{ResourceMonitor, ResourceUsage};
+use minecraft_manager::thread::MinecraftServerStopReasons;
+use minecraft_manager::{
+    self, tasks::MinecraftServerTask, MinecraftServerSettings, MinecraftServerType,
+};
 use serenity::utils::Colour;
 
-use std::collections::{HashMap, HashSet};
+use invocation::Invocation;
+
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use serenity::async_trait;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
 use serenity::model::channel::Message;
 use serenity::model::gateway::{Activity, Ready};
-use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::id::{ChannelId, GuildId, UserId};
 use serenity::prelude::*;
 
 struct Handler {
     is_running: Arc<AtomicBool>,
     should_run: AtomicBool,
     start_as: Arc<Mutex<String>>,
+    /// the mode of the running server (start_as can be changed while it is running)
+    running_mode: Arc<Mutex<Option<String>>>,
+    /// set by the refresh button to update the status message right away
+    refresh_status: Arc<AtomicBool>,
     task_sender: Arc<Mutex<Option<minecraft_manager::thread::MinecraftServerTaskSender>>>,
     bot_loop: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    /// players on the running server, with their uuid if it is known
+    players_online: Arc<Mutex<HashMap<String, Option<String>>>>,
+    /// resource usage of the (last) server process, sampled every 15 seconds
+    resources: Arc<Mutex<Option<ResourceMonitor>>>,
     my_ip: Arc<Mutex<String>>,
     status_channel_id: u64,
     chat_channel_id: u64,
     report_channel_id: u64,
-    server_configs: Arc<HashMap<String, (String, MinecraftServerSettings)>>,
+    server_configs: Arc<Mutex<HashMap<String, (String, MinecraftServerSettings)>>>,
+    /// path to the servers file, which is updated when configs are changed through commands
+    servers_file: String,
+    install_apis: install::InstallApis,
+    /// for mc.create: the directory containing the templates and the one where new servers are created
+    create_dirs: Option<(String, String)>,
+    /// player sessions, None if the database couldn't be opened
+    db: Option<Arc<Mutex<db::Db>>>,
+    /// if set, minecraft chat is sent through this webhook instead of as embeds
+    chat_webhook: Option<Arc<webhook::ChatWebhook>>,
+    /// minecraft name -> discord user id, for `@name` in minecraft chat
+    chat_pings: Arc<HashMap<String, u64>>,
+    /// mc.link codes which haven't been confirmed yet
+    pending_links: Mutex<link::PendingLinks>,
+    /// who can use which control commands
+    permissions: permissions::Permissions,
+    /// records who used which control command
+    audit: audit::AuditLog,
 }
 
 #[async_trait]
@@ -34,105 +90,244 @@ impl EventHandler for Handler {
             return;
         }
         let ctx = Arc::new(ctx);
+        if msg.channel_id.0 == self.chat_channel_id || msg.channel_id.0 == self.status_channel_id {
+            // works in both channels, and isn't relayed to minecraft
+            if msg.content.as_str() == "mc.link" || msg.content.starts_with("mc.link ") {
+                self.link(&ctx, &msg, &msg.content["mc.link".len()..]).await;
+                return;
+            }
+            if msg.content.as_str() == "mc.whitelist request"
+                || msg.content.starts_with("mc.whitelist request ")
+            {
+                self.whitelist_request(&ctx, &msg, &msg.content["mc.whitelist request".len()..])
+                    .await;
+                return;
+            }
+            if msg.content.as_str() == "mc.unlink" {
+                let reply = self.unlink(msg.author.id.0).await;
+                if let Err(e) = msg.reply(&ctx.http, reply).await {
+                    eprintln!("Error sending message: {:?}", e);
+                    metrics::discord_send_error();
+                }
+                return;
+            }
+        }
         if msg.channel_id.0 == self.chat_channel_id {
-            eprintln!(">> {}: '{}'", msg.author.name, msg.content);
-            if let Some(task_sender) = self.task_sender.lock().await.as_ref() {
-                let author = msg.author.name.as_str();
-                let content = msg.content_safe(&ctx.cache);
-                let mcmsg = format!("<{author}> {content}");
-                _ = task_sender.send_task(MinecraftServerTask::RunCommand(format!(
-                    "tellraw @a \"{}\"",
-                    mcmsg.replace("\\", "\\\\").replace("\"", "\\\"")
-                )));
+            if let (Some(id), Some(webhook)) = (msg.webhook_id, &self.chat_webhook) {
+                if id.0 == webhook.id {
+                    // minecraft chat we sent through the webhook
+                    return;
+                }
             }
-        } else if msg.channel_id.0 == self.status_channel_id {
-            if msg.content.as_str() == "mc..restart" {
-                ctx.shard.shutdown_clean();
-                std::thread::sleep(Duration::from_secs(2));
-                std::process::exit(0);
-            }
-            if msg.content.as_str().starts_with("mc.setmode ") {
-                let id = msg.content[11..].trim();
-                if self.server_configs.contains_key(id) {
-                    *self.start_as.lock().await = id.to_owned();
-                } else {
-                    if let Err(e) = msg
-                        .reply(
-                            &ctx.http,
-                            format!(
-                                "can't set mode to '{id}', try one of the following: {}",
-                                self.server_configs
-                                    .iter()
-                                    .map(|(id, (name, _cfg))| format!("'{id}' for {name}, "))
-                                    .collect::<String>(),
-                            )
-                            .as_str(),
-                        )
+            eprintln!(">> {}: '{}'", msg.author.name, msg.content);
+            if self.is_running.load(Ordering::Relaxed) && self.running_is_proxy().await {
+                eprintln!(
+                    "Not forwarding the message, proxy servers don't have a tellraw command."
+                );
+            } else if self.is_running.load(Ordering::Relaxed) {
+                let linked_name = match &self.db {
+                    Some(db) => db
+                        .lock()
                         .await
+                        .linked_account(msg.author.id.0)
+                        .ok()
+                        .flatten()
+                        .map(|(name, _uuid)| name),
+                    None => None,
+                };
+                let mcmsg = tellraw::message_components(&ctx, &msg, linked_name.as_deref()).await;
+                if let Some(task_sender) = self.task_sender.lock().await.as_ref() {
+                    if task_sender
+                        .send_task(MinecraftServerTask::Broadcast(mcmsg))
+                        .is_ok()
                     {
-                        eprintln!("Error sending message: {:?}", e);
+                        metrics::chat_to_minecraft();
                     }
                 }
             }
-            if msg.content.as_str() == "mc.start" {
+        } else if msg.channel_id.0 == self.status_channel_id {
+            let mut inv = Invocation::from_message(&msg);
+            let mut content = msg.content.trim();
+            // `--force` skips the confirmation when players are online. it's only removed from the
+            // commands which ask for confirmation, `mc.run` sends everything to the console.
+            if let Some(command) = content.strip_suffix(" --force") {
+                if matches!(command.trim_end(), "mc.stop" | "mc..restart") {
+                    inv.force = true;
+                    content = command.trim_end();
+                }
+            }
+            if let Some((command, mode)) = self.control_command(content).await {
+                if !self
+                    .check_permission(&ctx, &inv, command, mode.as_deref())
+                    .await
+                {
+                    return;
+                }
+            }
+            if content == "mc..restart" {
+                self.restart_bot(&ctx, &inv).await;
+            }
+            if let Some(id) = content.strip_prefix("mc.setmode ") {
+                self.set_mode(&ctx, &inv, id.trim()).await;
+            }
+            if content == "mc.start" {
+                self.start(ctx.clone(), &inv).await;
+            }
+            if content == "mc.eula accept" {
                 if !self.is_running.load(Ordering::Relaxed) {
-                    self.should_run.swap(true, Ordering::Relaxed);
-                    let status_message = msg
-                        .reply(
-                            &ctx.http,
-                            format!("starting {:?}...", self.start_as.lock().await.clone())
-                                .as_str(),
-                        )
+                    let start_as = self.start_as.lock().await.clone();
+                    let accepted = match self.server_configs.lock().await.get(&start_as) {
+                        Some((_name, cfg)) => cfg.accept_eula(),
+                        None => Err(std::io::Error::new(
+                            std::io::ErrorKind::NotFound,
+                            format!("no mode with id '{start_as}'"),
+                        )),
+                    };
+                    let outcome = match &accepted {
+                        Ok(()) => format!("accepted the EULA for {start_as}"),
+                        Err(e) => format!("couldn't edit eula.txt: {e}"),
+                    };
+                    self.audit
+                        .record(&ctx, &msg.author, &msg.content, &outcome)
                         .await;
-                    if let Err(why) = &status_message {
-                        eprintln!("Error sending message: {:?}", why);
+                    match accepted {
+                        Ok(()) => {
+                            if let Err(e) = msg
+                                .reply(&ctx.http, format!("accepted the EULA for {start_as}."))
+                                .await
+                            {
+                                eprintln!("Error sending message: {:?}", e);
+                                metrics::discord_send_error();
+                            }
+                            self.start(ctx.clone(), &inv).await;
+                        }
+                        Err(e) => {
+                            if let Err(e) = msg
+                                .reply(&ctx.http, format!("couldn't edit eula.txt: {e}"))
+                                .await
+                            {
+                                eprintln!("Error sending message: {:?}", e);
+                                metrics::discord_send_error();
+                            }
+                        }
                     }
-                    self.run_or_stop(ctx.clone(), status_message.ok()).await;
                 } else {
+                    self.audit
+                        .record(&ctx, &msg.author, &msg.content, "already running")
+                        .await;
                     if let Err(e) = msg.reply(&ctx.http, "server already running!").await {
                         eprintln!("Error sending message: {:?}", e);
+                        metrics::discord_send_error();
                     }
                 }
+            } else if content.starts_with("mc.eula") {
+                if let Err(e) = msg
+                    .reply(
+                        &ctx.http,
+                        "read the minecraft EULA (https://aka.ms/MinecraftEULA), then use `mc.eula accept` to accept it for the current mode and start the server.",
+                    )
+                    .await
+                {
+                    eprintln!("Error sending message: {:?}", e);
+                    metrics::discord_send_error();
+                }
             }
-            if msg.content.as_str() == "mc.stop" {
-                if self.is_running.load(Ordering::Relaxed) {
-                    self.should_run.swap(false, Ordering::Relaxed);
-                    if let Err(why) = msg.reply(&ctx.http, "stopping...").await {
-                        eprintln!("Error sending message: {:?}", why);
-                    }
-                    self.run_or_stop(ctx.clone(), None).await;
+            if content == "mc.stop" {
+                self.stop(ctx.clone(), &inv).await;
+            }
+            if let Some(command) = content.strip_prefix("mc.run ") {
+                self.run_command(&ctx, &inv, command).await;
+            }
+            if let Some(args) = content.strip_prefix("mc.install ") {
+                self.install(&ctx, &msg, args).await;
+            }
+            if let Some(args) = content.strip_prefix("mc.create ") {
+                self.create(&ctx, &msg, args).await;
+            }
+            if content == "mc.java" {
+                let runtimes = minecraft_manager::java::discover();
+                let mut reply = if runtimes.is_empty() {
+                    "no java runtimes found.".to_owned()
                 } else {
-                    if let Err(e) = msg.reply(&ctx.http, "server not running!").await {
-                        eprintln!("Error sending message: {:?}", e);
-                    }
+                    runtimes
+                        .iter()
+                        .map(|r| format!("- {r}\n"))
+                        .collect::<String>()
+                };
+                let start_as = self.start_as.lock().await.clone();
+                if let Some((name, cfg)) = self.server_configs.lock().await.get(&start_as) {
+                    reply.push_str(&if let Some(java_cmd) = &cfg.java_cmd {
+                        format!("{name} uses {java_cmd} (java_cmd)")
+                    } else if let Some(required) = cfg.required_java_version() {
+                        match minecraft_manager::java::select(&runtimes, required) {
+                            Some(r) => format!("{name} needs java {required} and will use {r}"),
+                            None => format!("{name} needs java {required}, but it isn't installed! `java` will be used."),
+                        }
+                    } else {
+                        format!("{name} doesn't specify a java version (set java_version), `java` will be used.")
+                    });
+                }
+                if let Err(e) = msg.reply(&ctx.http, reply).await {
+                    eprintln!("Error sending message: {:?}", e);
+                    metrics::discord_send_error();
                 }
             }
-            if msg.content.as_str().starts_with("mc.run ") {
-                if self.is_running.load(Ordering::Relaxed) {
-                    let command = msg.content.as_str()[7..].to_string();
-                    let status_message = msg
-                        .reply(&ctx.http, format!("running command \"{command}\"."))
-                        .await;
-                    if let Err(why) = &status_message {
-                        eprintln!("Error sending message: {:?}", why);
-                    }
-                    if let Some(task_sender) = self.task_sender.lock().await.as_ref() {
-                        _ = task_sender
-                            .send_task(MinecraftServerTask::RunCommand(format!("{}", command)));
-                    } else {
-                        eprintln!("can't run command (can't get sender).");
+            if content == "mc.resources" {
+                let reply = if let Some(resources) = self.resources.lock().await.as_ref() {
+                    let now = Instant::now();
+                    let mut reply = format!("Server process (pid {}):\n", resources.pid());
+                    // newest first, and not too many lines for one message
+                    for usage in resources.history().rev().take(20) {
+                        let ago = now.duration_since(usage.time).as_secs();
+                        reply.push_str(&format!(
+                            "`-{}:{:02}` {}\n",
+                            ago / 60,
+                            ago % 60,
+                            format_resource_usage(usage)
+                        ));
                     }
+                    reply
+                } else {
+                    "no resource usage recorded yet (start the server first)".to_owned()
+                };
+                if let Err(e) = msg.reply(&ctx.http, reply).await {
+                    eprintln!("Error sending message: {:?}", e);
+                    metrics::discord_send_error();
+                }
+            }
+            if content == "mc.playtime" || content.starts_with("mc.playtime ") {
+                let player = content["mc.playtime".len()..].trim();
+                let reply = self.playtime(player).await;
+                if let Err(e) = msg.reply(&ctx.http, reply).await {
+                    eprintln!("Error sending message: {:?}", e);
+                    metrics::discord_send_error();
                 }
             }
-            if msg.content.as_str().starts_with("mc.status") {
+            if let Some(player) = content.strip_prefix("mc.seen ") {
+                let reply = self.seen(player.trim()).await;
+                if let Err(e) = msg.reply(&ctx.http, reply).await {
+                    eprintln!("Error sending message: {:?}", e);
+                    metrics::discord_send_error();
+                }
+            }
+            if content == "mc.top" || content.starts_with("mc.top ") {
+                let reply = self.top(&content["mc.top".len()..]).await;
+                if let Err(e) = msg.reply(&ctx.http, reply).await {
+                    eprintln!("Error sending message: {:?}", e);
+                    metrics::discord_send_error();
+                }
+            }
+            if content.starts_with("mc.status") {
                 let running = self.is_running.load(Ordering::Relaxed);
-                let start_as = &*self.start_as.lock().await;
+                let start_as = &self.start_as.lock().await.clone();
                 let status_message = msg
                     .reply(
                         &ctx.http,
                         format!(
                             "Mode: {} ({})\n{}",
-                            if let Some((name, _cfg)) = self.server_configs.get(start_as) {
+                            if let Some((name, _cfg)) =
+                                self.server_configs.lock().await.get(start_as)
+                            {
                                 name
                             } else {
                                 "<unknown>"
@@ -144,6 +339,7 @@ impl EventHandler for Handler {
                     .await;
                 if let Err(why) = &status_message {
                     eprintln!("Error sending message: {:?}", why);
+                    metrics::discord_send_error();
                 }
             }
         }
@@ -163,7 +359,11 @@ impl EventHandler for Handler {
             .await;
         if let Err(why) = message {
             eprintln!("Error sending ready message: {:?}", why);
+            metrics::discord_send_error();
         };
+        if let Err(e) = slash::register(&ctx, self.status_channel_id).await {
+            eprintln!("Couldn't register slash commands: {:?}", e);
+        }
         ctx.idle().await;
         let mut bot_loop = self.bot_loop.lock().await;
         if let Some(bl) = bot_loop.take() {
@@ -215,6 +415,35 @@ impl EventHandler for Handler {
         }));
     }
 
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        match interaction {
+            Interaction::MessageComponent(component) => {
+                if let Some((approve, request)) =
+                    whitelist::WhitelistRequest::from_custom_id(&component.data.custom_id)
+                {
+                    self.whitelist_button(&ctx, &component, approve, request)
+                        .await;
+                } else if let Some(button) =
+                    confirm::ConfirmButton::parse(&component.data.custom_id)
+                {
+                    self.confirm_button(Arc::new(ctx), &component, button).await;
+                } else if let Some(action) = status_buttons::StatusAction::parse(
+                    &component.data.custom_id,
+                    &component.data.values,
+                ) {
+                    self.status_button(Arc::new(ctx), &component, action).await;
+                }
+            }
+            Interaction::ApplicationCommand(command) if command.data.name == "mc" => {
+                self.slash_command(Arc::new(ctx), &command).await;
+            }
+            Interaction::Autocomplete(autocomplete) if autocomplete.data.name == "mc" => {
+                self.slash_autocomplete(&ctx, &autocomplete).await;
+            }
+            _ => (),
+        }
+    }
+
     // We use the cache_ready event just in case some cache operation is required in whatever use
     // case you have for this.
     async fn cache_ready(&self, _ctx: Context, _guilds: Vec<GuildId>) {
@@ -226,6 +455,464 @@ impl EventHandler for Handler {
 }
 
 impl Handler {
+    async fn start(&self, ctx: Arc<Context>, inv: &Invocation<'_>) {
+        if !self.is_running.load(Ordering::Relaxed) {
+            self.should_run.swap(true, Ordering::Relaxed);
+            let start_as = self.start_as.lock().await.clone();
+            self.audit
+                .record(&ctx, inv.user, &inv.action, &format!("starting {start_as}"))
+                .await;
+            let status_message = inv.reply(&ctx, format!("starting {start_as:?}...")).await;
+            self.run_or_stop(ctx.clone(), status_message).await;
+        } else {
+            self.audit
+                .record(&ctx, inv.user, &inv.action, "already running")
+                .await;
+            inv.reply(&ctx, "server already running!").await;
+        }
+    }
+    /// `/mc <start|stop|mode|run>`, which goes through the same permission checks as the text commands
+    async fn slash_command(&self, ctx: Arc<Context>, command: &ApplicationCommandInteraction) {
+        let Some(slash::Subcommand {
+            name: subcommand,
+            arg,
+            force,
+            ..
+        }) = slash::subcommand(&command.data.options)
+        else {
+            return;
+        };
+        let Some(text_command) = slash::text_command(subcommand, arg) else {
+            return;
+        };
+        let mut action = match arg {
+            Some(arg) => format!("/mc {subcommand} {arg}"),
+            None => format!("/mc {subcommand}"),
+        };
+        if force {
+            action.push_str(" force:true");
+        }
+        let mut inv = Invocation::from_command(command, action);
+        inv.force = force;
+        // like the text commands, only in the status channel
+        if command.channel_id.0 != self.status_channel_id {
+            if let Err(e) = command
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| {
+                            d.content(format!(
+                                "use this command in <#{}>.",
+                                self.status_channel_id
+                            ))
+                            .ephemeral(true)
+                        })
+                })
+                .await
+            {
+                eprintln!("Error sending message: {:?}", e);
+                metrics::discord_send_error();
+            }
+            return;
+        }
+        if let Some((permission, mode)) = self.control_command(&text_command).await {
+            if !self
+                .check_permission(&ctx, &inv, permission, mode.as_deref())
+                .await
+            {
+                return;
+            }
+        }
+        match (subcommand, arg) {
+            ("start", _) => self.start(ctx.clone(), &inv).await,
+            ("stop", _) => self.stop(ctx.clone(), &inv).await,
+            ("mode", Some(id)) => self.set_mode(&ctx, &inv, id.trim()).await,
+            ("run", Some(command)) => self.run_command(&ctx, &inv, command).await,
+            _ => (),
+        }
+        inv.finish(&ctx).await;
+    }
+    /// suggestions for `/mc mode` (modes) and `/mc run` (online players)
+    async fn slash_autocomplete(&self, ctx: &Context, autocomplete: &AutocompleteInteraction) {
+        let Some(slash::Subcommand {
+            name: subcommand,
+            arg: Some(value),
+            arg_focused: true,
+            ..
+        }) = slash::subcommand(&autocomplete.data.options)
+        else {
+            return;
+        };
+        let choices = match subcommand {
+            "mode" => {
+                let value = value.to_lowercase();
+                let mut choices: Vec<_> = self
+                    .server_configs
+                    .lock()
+                    .await
+                    .iter()
+                    .filter(|(id, (name, _cfg))| {
+                        id.to_lowercase().contains(&value) || name.to_lowercase().contains(&value)
+                    })
+                    .map(|(id, (name, _cfg))| (format!("{name} ({id})"), id.clone()))
+                    .collect();
+                choices.sort();
+                choices.truncate(25);
+                choices
+            }
+            "run" => slash::complete_player_names(value, self.players_online.lock().await.keys()),
+            _ => return,
+        };
+        if let Err(e) = autocomplete
+            .create_autocomplete_response(&ctx.http, |r| {
+                for (name, value) in choices {
+                    r.add_string_choice(name, value);
+                }
+                r
+            })
+            .await
+        {
+            eprintln!("Error sending autocomplete response: {:?}", e);
+            metrics::discord_send_error();
+        }
+    }
+    /// mc..restart: exits, so that whatever runs the bot can restart it
+    async fn restart_bot(&self, ctx: &Context, inv: &Invocation<'_>) {
+        if self
+            .ask_confirmation(ctx, inv, "restart-bot", "Restart the bot")
+            .await
+        {
+            return;
+        }
+        self.audit
+            .record(ctx, inv.user, &inv.action, "restarting the bot")
+            .await;
+        ctx.shard.shutdown_clean();
+        std::thread::sleep(Duration::from_secs(2));
+        std::process::exit(0);
+    }
+    /// asks the user to confirm the action (see `confirm::Confirmation`) if players are online,
+    /// unless it was forced. returns true if the action has to wait for confirmation.
+    async fn ask_confirmation(
+        &self,
+        ctx: &Context,
+        inv: &Invocation<'_>,
+        action: &str,
+        question: &str,
+    ) -> bool {
+        if inv.force || !self.is_running.load(Ordering::Relaxed) {
+            return false;
+        }
+        let mut online: Vec<_> = self.players_online.lock().await.keys().cloned().collect();
+        if online.is_empty() {
+            return false;
+        }
+        online.sort_unstable();
+        let online = format!(
+            "{} {} online: {}.",
+            online.len(),
+            if online.len() == 1 {
+                "player is"
+            } else {
+                "players are"
+            },
+            online
+                .iter()
+                .map(|p| format!("`{p}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        self.audit
+            .record(
+                ctx,
+                inv.user,
+                &inv.action,
+                "asked for confirmation (players online)",
+            )
+            .await;
+        let confirmation = confirm::Confirmation {
+            issued: db::now(),
+            user_id: inv.user.id.0,
+            action: action.to_owned(),
+        };
+        match confirmation.custom_id() {
+            Some(confirm_id) => {
+                inv.reply_with_components(
+                    ctx,
+                    format!(
+                        "{online}\n{question} anyway? confirm within {} seconds, or use the command again with `--force`.",
+                        confirm::CONFIRM_TIMEOUT
+                    ),
+                    |c| confirm::buttons(c, &confirm_id, inv.user.id.0),
+                )
+                .await;
+            }
+            None => {
+                inv.reply(
+                    ctx,
+                    format!("{online}\nuse the command again with `--force` to do it anyway."),
+                )
+                .await;
+            }
+        }
+        true
+    }
+    /// the confirm and cancel buttons from `ask_confirmation`
+    async fn confirm_button(
+        &self,
+        ctx: Arc<Context>,
+        component: &MessageComponentInteraction,
+        button: confirm::ConfirmButton,
+    ) {
+        let user_id = match &button {
+            confirm::ConfirmButton::Confirm(confirmation) => confirmation.user_id,
+            confirm::ConfirmButton::Cancel { user_id } => *user_id,
+        };
+        // replaces the message with the buttons
+        let http = &ctx.http;
+        let update = |content: String| async move {
+            if let Err(e) = component
+                .create_interaction_response(http, |r| {
+                    r.kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|d| d.content(content).components(|c| c))
+                })
+                .await
+            {
+                eprintln!("Error sending message: {:?}", e);
+                metrics::discord_send_error();
+            }
+        };
+        if component.user.id.0 != user_id {
+            if let Err(e) = component
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| {
+                            d.content(format!("only <@{user_id}> can confirm or cancel this."))
+                                .ephemeral(true)
+                        })
+                })
+                .await
+            {
+                eprintln!("Error sending message: {:?}", e);
+                metrics::discord_send_error();
+            }
+            return;
+        }
+        let confirmation = match button {
+            confirm::ConfirmButton::Cancel { .. } => {
+                update(format!("{}\ncancelled.", component.message.content)).await;
+                return;
+            }
+            confirm::ConfirmButton::Confirm(confirmation) => confirmation,
+        };
+        if confirmation.is_expired(db::now()) {
+            update(format!(
+                "{}\nthis has expired, use the command again.",
+                component.message.content
+            ))
+            .await;
+            return;
+        }
+        let text_commands = match confirmation.action.as_str() {
+            "stop" => vec!["mc.stop".to_owned()],
+            "restart" => vec!["mc.stop".to_owned(), "mc.start".to_owned()],
+            "restart-bot" => vec!["mc..restart".to_owned()],
+            _ => return,
+        };
+        // the buttons can only be used once. the response has to be a new message,
+        // because it can become the status message.
+        if let Err(e) = component
+            .message
+            .channel_id
+            .edit_message(&ctx.http, component.message.id, |m| m.components(|c| c))
+            .await
+        {
+            eprintln!("Error sending message: {:?}", e);
+            metrics::discord_send_error();
+        }
+        let mut inv =
+            Invocation::from_component(component, format!("confirmed: {}", confirmation.action));
+        inv.force = true;
+        for text_command in &text_commands {
+            if let Some((permission, mode)) = self.control_command(text_command).await {
+                if !self
+                    .check_permission(&ctx, &inv, permission, mode.as_deref())
+                    .await
+                {
+                    return;
+                }
+            }
+        }
+        match confirmation.action.as_str() {
+            "stop" => self.stop(ctx.clone(), &inv).await,
+            "restart" => self.restart(ctx.clone(), &inv).await,
+            "restart-bot" => self.restart_bot(&ctx, &inv).await,
+            _ => (),
+        }
+        inv.finish(&ctx).await;
+    }
+    /// the buttons and mode select menu on the status message, which go through the same
+    /// permission checks as the text commands
+    async fn status_button(
+        &self,
+        ctx: Arc<Context>,
+        component: &MessageComponentInteraction,
+        action: status_buttons::StatusAction,
+    ) {
+        use status_buttons::StatusAction;
+        let (action_text, text_commands) = match &action {
+            StatusAction::Start => ("start".to_owned(), vec!["mc.start".to_owned()]),
+            StatusAction::Stop => ("stop".to_owned(), vec!["mc.stop".to_owned()]),
+            StatusAction::Restart => (
+                "restart".to_owned(),
+                vec!["mc.stop".to_owned(), "mc.start".to_owned()],
+            ),
+            StatusAction::Mode(id) => (format!("mode {id}"), vec![format!("mc.setmode {id}")]),
+            StatusAction::Refresh => {
+                // the status message is updated by the server's loop
+                self.refresh_status.store(true, Ordering::Relaxed);
+                if let Err(e) = component
+                    .create_interaction_response(&ctx.http, |r| {
+                        r.kind(InteractionResponseType::DeferredUpdateMessage)
+                    })
+                    .await
+                {
+                    eprintln!("Error sending message: {:?}", e);
+                    metrics::discord_send_error();
+                }
+                return;
+            }
+        };
+        let inv = Invocation::from_component(component, format!("[status message] {action_text}"));
+        for text_command in &text_commands {
+            if let Some((permission, mode)) = self.control_command(text_command).await {
+                if !self
+                    .check_permission(&ctx, &inv, permission, mode.as_deref())
+                    .await
+                {
+                    return;
+                }
+            }
+        }
+        match action {
+            StatusAction::Start => self.start(ctx.clone(), &inv).await,
+            StatusAction::Stop => self.stop(ctx.clone(), &inv).await,
+            StatusAction::Restart => self.restart(ctx.clone(), &inv).await,
+            StatusAction::Mode(id) => self.set_mode(&ctx, &inv, &id).await,
+            StatusAction::Refresh => (),
+        }
+        inv.finish(&ctx).await;
+    }
+    /// stops the server, then starts it again in the current mode
+    async fn restart(&self, ctx: Arc<Context>, inv: &Invocation<'_>) {
+        if !self.is_running.load(Ordering::Relaxed) {
+            self.audit
+                .record(&ctx, inv.user, &inv.action, "not running")
+                .await;
+            inv.reply(&ctx, "server not running!").await;
+            return;
+        }
+        if self
+            .ask_confirmation(&ctx, inv, "restart", "Restart the server")
+            .await
+        {
+            return;
+        }
+        self.should_run.swap(false, Ordering::Relaxed);
+        self.audit
+            .record(&ctx, inv.user, &inv.action, "restarting")
+            .await;
+        inv.reply(&ctx, "restarting...").await;
+        self.run_or_stop(ctx.clone(), None).await;
+        // the old server's loop has to finish before a new server can be started
+        for _ in 0..120 {
+            if self.running_mode.lock().await.is_none() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        if self.running_mode.lock().await.is_some() {
+            inv.reply(&ctx, "the server didn't stop, so it wasn't started again.")
+                .await;
+            return;
+        }
+        self.start(ctx, inv).await;
+    }
+    async fn stop(&self, ctx: Arc<Context>, inv: &Invocation<'_>) {
+        if self
+            .ask_confirmation(&ctx, inv, "stop", "Stop the server")
+            .await
+        {
+            return;
+        }
+        if self.is_running.load(Ordering::Relaxed) {
+            self.should_run.swap(false, Ordering::Relaxed);
+            self.audit
+                .record(&ctx, inv.user, &inv.action, "stopping")
+                .await;
+            inv.reply(&ctx, "stopping...").await;
+            self.run_or_stop(ctx.clone(), None).await;
+        } else {
+            self.audit
+                .record(&ctx, inv.user, &inv.action, "not running")
+                .await;
+            inv.reply(&ctx, "server not running!").await;
+        }
+    }
+    /// selects the mode which is used the next time the server starts
+    async fn set_mode(&self, ctx: &Context, inv: &Invocation<'_>, id: &str) {
+        // start_as and server_configs are never locked at the same time, which could deadlock
+        let name = self
+            .server_configs
+            .lock()
+            .await
+            .get(id)
+            .map(|(name, _cfg)| name.clone());
+        if let Some(name) = name {
+            *self.start_as.lock().await = id.to_owned();
+            self.audit
+                .record(ctx, inv.user, &inv.action, &format!("mode set to {id}"))
+                .await;
+            inv.reply(ctx, format!("mode set to {name} ({id}).")).await;
+        } else {
+            self.audit
+                .record(ctx, inv.user, &inv.action, "unknown mode")
+                .await;
+            let modes = self
+                .server_configs
+                .lock()
+                .await
+                .iter()
+                .map(|(id, (name, _cfg))| format!("'{id}' for {name}, "))
+                .collect::<String>();
+            inv.reply(
+                ctx,
+                format!("can't set mode to '{id}', try one of the following: {modes}"),
+            )
+            .await;
+        }
+    }
+    /// runs a command in the server's console
+    async fn run_command(&self, ctx: &Context, inv: &Invocation<'_>, command: &str) {
+        if self.is_running.load(Ordering::Relaxed) {
+            inv.reply(ctx, format!("running command \"{command}\"."))
+                .await;
+            let outcome = if let Some(task_sender) = self.task_sender.lock().await.as_ref() {
+                match task_sender.send_task(MinecraftServerTask::RunCommand(command.to_owned())) {
+                    Ok(_) => "sent to the server",
+                    Err(_) => "couldn't send it to the server",
+                }
+            } else {
+                eprintln!("can't run command (can't get sender).");
+                "couldn't send it to the server"
+            };
+            self.audit.record(ctx, inv.user, &inv.action, outcome).await;
+        } else {
+            self.audit
+                .record(ctx, inv.user, &inv.action, "not running")
+                .await;
+            inv.reply(ctx, "server not running!").await;
+        }
+    }
     async fn run_or_stop(&self, ctx: Arc<Context>, mut status_message: Option<Message>) {
         // create the minecraft server
 
@@ -247,9 +934,11 @@ impl Handler {
                 let ctx = ctx.clone();
                 let arc_sender = self.task_sender.clone();
                 let arc_is_running = self.is_running.clone();
-                let started_as = &*self.start_as.lock().await;
+                let arc_resources = self.resources.clone();
+                let arc_players_online = self.players_online.clone();
+                let started_as = &self.start_as.lock().await.clone();
                 let (display_name, minecraft_server_settings) =
-                    match self.server_configs.get(started_as) {
+                    match self.server_configs.lock().await.get(started_as) {
                         Some(v) => v.clone(),
                         None => {
                             self.should_run.store(false, Ordering::Relaxed);
@@ -258,6 +947,15 @@ impl Handler {
                     };
                 let ip_mutex = self.my_ip.clone();
                 let chat_channel_id = self.chat_channel_id;
+                let mode = started_as.clone();
+                *self.running_mode.lock().await = Some(mode.clone());
+                let arc_running_mode = self.running_mode.clone();
+                let arc_refresh_status = self.refresh_status.clone();
+                let arc_start_as = self.start_as.clone();
+                let arc_server_configs = self.server_configs.clone();
+                let db = self.db.clone();
+                let chat_webhook = self.chat_webhook.clone();
+                let chat_pings = self.chat_pings.clone();
                 let status_channel_id = self.status_channel_id;
                 tokio::spawn(async move {
                     // create minecraft server config
@@ -270,18 +968,56 @@ impl Handler {
                     // };
                     // start server
                     let mut thread = minecraft_server_settings.spawn();
+                    metrics::server_started(&mode);
                     let thread_task_sender = thread.clone_task_sender();
                     *arc_sender.lock().await = Some(thread_task_sender);
                     // handle stdout
-                    let mut players_online = HashSet::new();
+                    arc_players_online.lock().await.clear();
+                    // for proxies: which backend server each player is connected to
+                    let mut player_backends = HashMap::new();
                     let mut any_changes = true;
                     let mut last_changes = Instant::now();
+                    let mut last_resource_sample: Option<Instant> = None;
+                    let mut last_heartbeat: Option<Instant> = None;
+                    let mut lag_tracker = lag::LagTracker::default();
+                    let mut mspt = None;
                     loop {
                         std::thread::sleep(std::time::Duration::from_millis(200));
                         if !thread.is_finished() {
                             thread.update();
+                            if last_resource_sample.is_none_or(|t| t.elapsed().as_secs() >= 15) {
+                                if let Some(pid) = thread.pid() {
+                                    let mut resources = arc_resources.lock().await;
+                                    if resources.as_ref().map(|r| r.pid()) != Some(pid) {
+                                        // 10 minutes of history
+                                        *resources = Some(ResourceMonitor::new(pid, 40));
+                                    }
+                                    if let Some(resources) = resources.as_mut() {
+                                        match resources.sample() {
+                                            Ok(usage) => {
+                                                metrics::process_memory(&mode, usage.rss_bytes)
+                                            }
+                                            Err(e) => {
+                                                eprintln!("Couldn't sample resource usage: {e}")
+                                            }
+                                        }
+                                    }
+                                    last_resource_sample = Some(Instant::now());
+                                    any_changes = true;
+                                }
+                            }
+                            // if the bot crashes, open sessions are ended at the last heartbeat
+                            if last_heartbeat.is_none_or(|t| t.elapsed().as_secs() >= 60) {
+                                if let Some(db) = &db {
+                                    if let Err(e) = db.lock().await.heartbeat(db::now()) {
+                                        eprintln!("Couldn't save heartbeat: {e}");
+                                    }
+                                }
+                                last_heartbeat = Some(Instant::now());
+                            }
                             for event in thread.handle_new_events() {
                                 eprintln!("[SRV:] {:?}", event);
+                                metrics::event(&event.event);
                                 match &event.event {
                                     MinecraftServerEventType::Warning(w) => {
                                         eprintln!("Warning: {w:?}");
@@ -297,51 +1033,116 @@ impl Handler {
                                             .await
                                         {
                                             eprintln!("Couldn't send message: {e:?}");
+                                            metrics::discord_send_error();
                                         }
                                     }
                                     MinecraftServerEventType::JoinLeave(ev) => {
+                                        if let Some(db) = &db {
+                                            let db = db.lock().await;
+                                            let saved = if ev.joined {
+                                                db.player_joined(
+                                                    &mode,
+                                                    &ev.username,
+                                                    ev.uuid.as_deref(),
+                                                    db::now(),
+                                                )
+                                            } else {
+                                                db.player_left(&mode, &ev.username, db::now())
+                                            };
+                                            if let Err(e) = saved {
+                                                eprintln!("Couldn't save session: {e}");
+                                            }
+                                        }
+                                        let mut players_online = arc_players_online.lock().await;
                                         if ev.joined {
-                                            players_online.insert(ev.username.to_string());
+                                            players_online
+                                                .insert(ev.username.to_string(), ev.uuid.clone());
                                         } else {
                                             players_online.remove(&ev.username);
+                                            player_backends.remove(&ev.username);
+                                        }
+                                        metrics::players_online(&mode, players_online.len());
+                                        any_changes = true;
+                                    }
+                                    // the server will (usually) exit right after this, and the stop reason explains it
+                                    MinecraftServerEventType::EulaNotAccepted
+                                    | MinecraftServerEventType::StartupFailure(_) => (),
+                                    // the server thread adds the uuid to join/leave and chat events
+                                    MinecraftServerEventType::PlayerUuid(_) => (),
+                                    MinecraftServerEventType::Lag(ev) => {
+                                        if let Some(warning) = lag_tracker.on_lag(ev) {
+                                            send_lag_warning(&ctx, status_channel_id, warning)
+                                                .await;
+                                        }
+                                    }
+                                    MinecraftServerEventType::Tps(ev) => {
+                                        if let Some(warning) = lag_tracker.on_tps(ev) {
+                                            send_lag_warning(&ctx, status_channel_id, warning)
+                                                .await;
                                         }
                                         any_changes = true;
                                     }
+                                    MinecraftServerEventType::Mspt(ev) => {
+                                        mspt = Some(ev.clone());
+                                        any_changes = true;
+                                    }
+                                    MinecraftServerEventType::BackendSwitch(ev) => {
+                                        player_backends
+                                            .insert(ev.username.to_string(), ev.server.to_string());
+                                        any_changes = true;
+                                    }
                                     MinecraftServerEventType::ChatMessage(ev) => {
-                                        let message = ChannelId(chat_channel_id)
-                                            .send_message(&ctx.http, |m| {
-                                                m.embed(|e| {
-                                                    e.set_author(
-                                                        serenity::builder::CreateEmbedAuthor({
-                                                            let mut hm =
-                                                                std::collections::HashMap::new();
-                                                            hm.insert(
-                                                                "name",
-                                                                ev.author.as_str().into(),
-                                                            );
-                                                            // hm.insert(
-                                                            //     "iconURL",
-                                                            //     "https://i.imgur.com/AfFp7pu.png"
-                                                            //         .into(),
-                                                            // );
-                                                            // hm.insert(
-                                                            //     "url",
-                                                            //     "https://discord.js.org".into(),
-                                                            // );
-                                                            hm
-                                                        }),
-                                                    )
-                                                    .description(ev.message.as_str())
-                                                })
-                                            })
-                                            .await;
-                                        if let Err(why) = message {
-                                            eprintln!("Error sending message: {:?}", why);
+                                        // players in mcdcbot_chat_pings, or who linked their account
+                                        let links = match &db {
+                                            Some(db) => Some(db.lock().await),
+                                            None => None,
                                         };
+                                        let text =
+                                            discord_text::from_minecraft(&ev.message, |name| {
+                                                chat_pings
+                                                    .get(&name.to_lowercase())
+                                                    .copied()
+                                                    .or_else(|| {
+                                                        links.as_ref().and_then(|db| {
+                                                            db.linked_discord_id(name)
+                                                                .ok()
+                                                                .flatten()
+                                                        })
+                                                    })
+                                            });
+                                        drop(links);
+                                        let message = if let Some(webhook) = &chat_webhook {
+                                            webhook
+                                                .send(
+                                                    &ctx.http,
+                                                    &ev.author,
+                                                    ev.uuid.as_deref(),
+                                                    &text,
+                                                )
+                                                .await
+                                        } else {
+                                            send_chat_embed(
+                                                &ctx,
+                                                chat_channel_id,
+                                                &ev.author,
+                                                &text,
+                                            )
+                                            .await
+                                        };
+                                        match message {
+                                            Ok(_) => metrics::chat_to_discord(),
+                                            Err(why) => {
+                                                eprintln!("Error sending message: {:?}", why);
+                                                metrics::discord_send_error();
+                                            }
+                                        }
                                     }
                                 }
                             }
-                            if any_changes || last_changes.elapsed().as_secs_f64() > 15.0 {
+                            if any_changes
+                                || arc_refresh_status.swap(false, Ordering::Relaxed)
+                                || last_changes.elapsed().as_secs_f64() > 15.0
+                            {
                                 any_changes = false;
                                 last_changes = Instant::now();
                                 if let Some(msg) = &mut status_message {
@@ -349,16 +1150,24 @@ impl Handler {
                                         "IP: {}\nPlayers online: {}\nChat: <#{}>",
                                         ip_mutex.lock().await.as_str(),
                                         {
+                                            let players_online = arc_players_online.lock().await;
                                             let mut online: Vec<_> =
-                                                players_online.iter().collect();
+                                                players_online.keys().collect();
                                             online.sort_unstable();
                                             let lenm1 = online.len().saturating_sub(1);
                                             online
                                                 .into_iter()
                                                 .enumerate()
                                                 .map(|(i, v)| {
-                                                    if i == 0 {
+                                                    let v = if let Some(server) =
+                                                        player_backends.get(v)
+                                                    {
+                                                        format!("{v} ({server})")
+                                                    } else {
                                                         v.to_string()
+                                                    };
+                                                    if i == 0 {
+                                                        v
                                                     } else if i == lenm1 {
                                                         format!(" and {v}")
                                                     } else {
@@ -369,6 +1178,39 @@ impl Handler {
                                         },
                                         chat_channel_id
                                     );
+                                    if let Some(tps) = &lag_tracker.tps {
+                                        desc.push_str(
+                                            format!(
+                                                "\nTPS (1/5/15min): {:.1}, {:.1}, {:.1}",
+                                                tps.one_minute,
+                                                tps.five_minutes,
+                                                tps.fifteen_minutes
+                                            )
+                                            .as_str(),
+                                        );
+                                    }
+                                    if let Some(mspt) = &mspt {
+                                        desc.push_str(
+                                            format!(
+                                                "\nMSPT (5s/10s/1min): {:.1}, {:.1}, {:.1}",
+                                                mspt.five_seconds,
+                                                mspt.ten_seconds,
+                                                mspt.one_minute
+                                            )
+                                            .as_str(),
+                                        );
+                                    }
+                                    if let Some(usage) =
+                                        arc_resources.lock().await.as_ref().and_then(|r| r.latest())
+                                    {
+                                        desc.push_str(
+                                            format!(
+                                                "\nServer process: {}",
+                                                format_resource_usage(usage)
+                                            )
+                                            .as_str(),
+                                        );
+                                    }
                                     if let Ok(mem) = sys_info::mem_info() {
                                         let percentage =
                                             100.0 * mem.avail as f64 / mem.total as f64;
@@ -397,6 +1239,7 @@ impl Handler {
                                                 ))
                                                 .description(desc)
                                             })
+                                            .components(status_buttons::running)
                                         })
                                         .await;
                                 }
@@ -416,7 +1259,11 @@ impl Handler {
                                         e.colour(Colour::from_rgb(160, 0, 255))
                                             .title("server stopped")
                                             .description(if let Ok(r) = stop_reason {
-                                                format!("{r}")
+                                                if let MinecraftServerStopReasons::EulaNotAccepted(_) = r.reason() {
+                                                    format!("{r}\nRead the EULA (https://aka.ms/MinecraftEULA), then use `mc.eula accept` to accept it and start the server.")
+                                                } else {
+                                                    format!("{r}")
+                                                }
                                             } else {
                                                 "(no reason given)".to_owned()
                                             })
@@ -425,18 +1272,38 @@ impl Handler {
                                 .await
                             {
                                 eprintln!("Couldn't send message: {e:?}");
+                                metrics::discord_send_error();
                             }
                             if let Some(msg) = &mut status_message {
+                                let mut modes: Vec<_> = arc_server_configs
+                                    .lock()
+                                    .await
+                                    .iter()
+                                    .map(|(id, (name, _cfg))| (id.clone(), name.clone()))
+                                    .collect();
+                                modes.sort();
+                                let selected = arc_start_as.lock().await.clone();
                                 _ = msg
                                     .edit(&ctx.http, |m| {
                                         m.embed(|e| {
                                             e.colour(Colour::from_rgb(15, 0, 45))
                                                 .description(format!("{} (stopped)", display_name))
                                         })
+                                        .components(|c| {
+                                            status_buttons::stopped(c, &modes, &selected)
+                                        })
                                     })
                                     .await;
                             }
                             ctx.idle().await;
+                            metrics::server_stopped(&mode);
+                            arc_players_online.lock().await.clear();
+                            if let Some(db) = &db {
+                                if let Err(e) = db.lock().await.server_stopped(&mode, db::now()) {
+                                    eprintln!("Couldn't end sessions: {e}");
+                                }
+                            }
+                            *arc_running_mode.lock().await = None;
                             arc_is_running.swap(false, Ordering::Relaxed);
                             break;
                         }
@@ -494,6 +1361,717 @@ impl Handler {
             // Now that the loop is running, we set the bool to true
         }
     }
+    /// the permission name and mode of a control command, see `permissions`. None for other messages.
+    async fn control_command(&self, content: &str) -> Option<(&'static str, Option<String>)> {
+        let arg = |prefix: &str| {
+            content
+                .strip_prefix(prefix)
+                .and_then(|args| args.split_whitespace().next())
+                .map(|v| v.to_owned())
+        };
+        Some(match content {
+            "mc..restart" => ("restart", None),
+            // accepting the eula starts the server
+            "mc.start" | "mc.eula accept" => ("start", Some(self.start_as.lock().await.clone())),
+            "mc.stop" => ("stop", self.running_mode.lock().await.clone()),
+            _ if content.starts_with("mc.run ") => ("run", self.running_mode.lock().await.clone()),
+            _ if content.starts_with("mc.setmode ") => ("setmode", arg("mc.setmode ")),
+            _ if content.starts_with("mc.install ") => ("install", arg("mc.install ")),
+            _ if content.starts_with("mc.create ") => ("create", arg("mc.create ")),
+            _ => return None,
+        })
+    }
+    /// replies and records the attempt if the user isn't allowed to use the command
+    async fn check_permission(
+        &self,
+        ctx: &Context,
+        inv: &Invocation<'_>,
+        command: &str,
+        mode: Option<&str>,
+    ) -> bool {
+        if self
+            .is_allowed(command, mode, inv.user.id.0, &inv.role_ids)
+            .await
+        {
+            return true;
+        }
+        let reply = match mode {
+            Some(mode) => format!("you don't have permission to use mc.{command} on {mode}."),
+            None => format!("you don't have permission to use mc.{command}."),
+        };
+        inv.reply(ctx, reply).await;
+        self.audit
+            .record(ctx, inv.user, &inv.action, "denied (no permission)")
+            .await;
+        false
+    }
+    /// checks the permissions file, looking up the user's linked minecraft account if a rule needs it
+    async fn is_allowed(
+        &self,
+        command: &str,
+        mode: Option<&str>,
+        user_id: u64,
+        role_ids: &[u64],
+    ) -> bool {
+        let needs_player = self.permissions.rule(command, mode).is_some_and(|rule| {
+            rule.iter()
+                .any(|a| matches!(a, permissions::Allowed::Player(_)))
+        });
+        let linked_player = match &self.db {
+            Some(db) if needs_player => db
+                .lock()
+                .await
+                .linked_account(user_id)
+                .ok()
+                .flatten()
+                .map(|(name, _uuid)| name),
+            _ => None,
+        };
+        self.permissions
+            .allows(command, mode, user_id, role_ids, linked_player.as_deref())
+    }
+    /// mc.playtime [player]: the player's playtime per mode, or the players with the most playtime
+    async fn playtime(&self, player: &str) -> String {
+        let Some(db) = &self.db else {
+            return "player sessions aren't being recorded (the database couldn't be opened)"
+                .to_owned();
+        };
+        let server_configs = self.server_configs.lock().await;
+        let mode_name = |mode: &str| match server_configs.get(mode) {
+            Some((name, _cfg)) => name.clone(),
+            None => mode.to_owned(),
+        };
+        let db = db.lock().await;
+        if player.is_empty() {
+            match db.top_playtime(10, db::now()) {
+                Ok(top) if top.is_empty() => "no sessions recorded yet.".to_owned(),
+                Ok(top) => top
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (player, seconds))| {
+                        format!("{}. {player}: {}\n", i + 1, db::format_duration(*seconds))
+                    })
+                    .collect(),
+                Err(e) => format!("couldn't read sessions: {e}"),
+            }
+        } else {
+            match db.playtime(player, db::now()) {
+                Ok(playtime) if playtime.is_empty() => format!("{player} has never played here."),
+                Ok(playtime) => {
+                    let total = playtime.iter().map(|p| p.seconds).sum();
+                    let mut reply =
+                        format!("{player} has played {}:\n", db::format_duration(total));
+                    for p in playtime {
+                        reply.push_str(&format!(
+                            "- {}: {}\n",
+                            mode_name(&p.mode),
+                            db::format_duration(p.seconds)
+                        ));
+                    }
+                    reply
+                }
+                Err(e) => format!("couldn't read sessions: {e}"),
+            }
+        }
+    }
+    /// mc.seen <player>
+    async fn seen(&self, player: &str) -> String {
+        let Some(db) = &self.db else {
+            return "player sessions aren't being recorded (the database couldn't be opened)"
+                .to_owned();
+        };
+        let seen = db.lock().await.seen(player);
+        let server_configs = self.server_configs.lock().await;
+        let mode_name = |mode: &str| match server_configs.get(mode) {
+            Some((name, _cfg)) => name.clone(),
+            None => mode.to_owned(),
+        };
+        match seen {
+            Ok(db::Seen::Online(mode, joined)) => format!(
+                "{player} is online on {} (for {}).",
+                mode_name(&mode),
+                db::format_duration(db::now() - joined)
+            ),
+            Ok(db::Seen::Offline(mode, left)) => format!(
+                "{player} was last seen on {} {} ago.",
+                mode_name(&mode),
+                db::format_duration(db::now() - left)
+            ),
+            Ok(db::Seen::Never) => format!("{player} has never played here."),
+            Err(e) => format!("couldn't read sessions: {e}"),
+        }
+    }
+    /// mc.top <stat> [mode]: leaderboard from the world's stats files, for the current mode by default
+    async fn top(&self, args: &str) -> String {
+        let args: Vec<_> = args.split_whitespace().collect();
+        let (stat, mode) = match args[..] {
+            [stat] => (stat, self.start_as.lock().await.clone()),
+            [stat, mode] => (stat, mode.to_owned()),
+            _ => {
+                return format!(
+                    "usage: mc.top <stat> [mode], stat is one of {}",
+                    stats::Stat::NAMES
+                )
+            }
+        };
+        let Some(stat) = stats::Stat::parse(stat) else {
+            return format!("unknown stat '{stat}', try one of {}", stats::Stat::NAMES);
+        };
+        let (name, directory) = match self.server_configs.lock().await.get(&mode) {
+            Some((name, cfg)) => (name.clone(), cfg.directory.clone()),
+            None => return format!("there is no mode with id '{mode}'"),
+        };
+        match stats::leaderboard(&directory, stat) {
+            Ok(board) if board.is_empty() => format!("no stats on {name} yet."),
+            Ok(board) => {
+                let mut reply = format!("Top {stat} on {name}:\n");
+                for (i, (player, value)) in board.iter().take(10).enumerate() {
+                    reply.push_str(&format!("{}. {player}: {}\n", i + 1, stat.format(*value)));
+                }
+                reply
+            }
+            Err(e) => format!("{e}"),
+        }
+    }
+    /// mc.link [<minecraft name> | confirm <code>]: sends a code to the player in minecraft,
+    /// which links the accounts once it is confirmed on discord
+    async fn link(&self, ctx: &Context, msg: &Message, args: &str) {
+        let reply = |content: String| async move {
+            if let Err(e) = msg.reply(&ctx.http, content).await {
+                eprintln!("Error sending message: {:?}", e);
+                metrics::discord_send_error();
+            }
+        };
+        let Some(db) = &self.db else {
+            reply("accounts can't be linked (the database couldn't be opened)".to_owned()).await;
+            return;
+        };
+        let discord_id = msg.author.id.0;
+        let args: Vec<_> = args.split_whitespace().collect();
+        match args[..] {
+            [] => {
+                let reply_content = match db.lock().await.linked_account(discord_id) {
+                    Ok(Some((name, _uuid))) => {
+                        format!("you are linked to {name}. use `mc.unlink` to remove the link.")
+                    }
+                    Ok(None) => "you haven't linked a minecraft account yet. join the server and use `mc.link <minecraft name>`.".to_owned(),
+                    Err(e) => format!("couldn't read links: {e}"),
+                };
+                reply(reply_content).await;
+            }
+            ["confirm", code] => {
+                let reply_content = match self.pending_links.lock().await.confirm(discord_id, code)
+                {
+                    Ok((name, uuid)) => {
+                        match db
+                            .lock()
+                            .await
+                            .link(discord_id, &name, uuid.as_deref(), db::now())
+                        {
+                            Ok(()) => format!("you are now linked to {name}."),
+                            Err(e) => format!("couldn't save the link: {e}"),
+                        }
+                    }
+                    Err(e) => format!("{e}"),
+                };
+                reply(reply_content).await;
+            }
+            [name] => {
+                if self.running_is_proxy().await {
+                    reply("linking isn't supported on proxy servers, the code can't be sent in minecraft. link your account while a normal server is running.".to_owned()).await;
+                    return;
+                }
+                if !minecraft_manager::is_valid_player_name(name) {
+                    reply(format!("'{name}' isn't a valid minecraft name")).await;
+                    return;
+                }
+                let player = self
+                    .players_online
+                    .lock()
+                    .await
+                    .iter()
+                    .find(|(player, _uuid)| player.eq_ignore_ascii_case(name))
+                    .map(|(player, uuid)| (player.clone(), uuid.clone()));
+                let Some((name, uuid)) = player else {
+                    reply(format!(
+                        "{name} isn't online, join the server first so the code can be sent to you"
+                    ))
+                    .await;
+                    return;
+                };
+                let code = self
+                    .pending_links
+                    .lock()
+                    .await
+                    .start(discord_id, name.clone(), uuid);
+                let message = vec![
+                    TextComponent::new(format!("{} ", msg.author.tag())).with_color("aqua"),
+                    TextComponent::new(
+                        "wants to link their discord account to yours. If that's you, send ",
+                    ),
+                    TextComponent::new(format!("mc.link confirm {code}")).with_color("gold"),
+                    TextComponent::new(format!(
+                        " on discord within {} minutes. Otherwise, ignore this message.",
+                        link::CODE_TIMEOUT.as_secs() / 60
+                    )),
+                ];
+                let sent = match self.task_sender.lock().await.as_ref() {
+                    Some(task_sender) => task_sender
+                        .send_task(MinecraftServerTask::Tell(name.clone(), message))
+                        .is_ok(),
+                    None => false,
+                };
+                if sent {
+                    reply(format!(
+                        "sent a code to {name} in minecraft, confirm it here with `mc.link confirm <code>`."
+                    ))
+                    .await;
+                } else {
+                    reply("couldn't send the code, is the server running?".to_owned()).await;
+                }
+            }
+            _ => {
+                reply("usage: mc.link [<minecraft name> | confirm <code>]".to_owned()).await;
+            }
+        }
+    }
+    /// mc.unlink
+    async fn unlink(&self, discord_id: u64) -> String {
+        let Some(db) = &self.db else {
+            return "accounts can't be linked (the database couldn't be opened)".to_owned();
+        };
+        match db.lock().await.unlink(discord_id) {
+            Ok(true) => "your minecraft account has been unlinked.".to_owned(),
+            Ok(false) => "you haven't linked a minecraft account.".to_owned(),
+            Err(e) => format!("couldn't remove the link: {e}"),
+        }
+    }
+    /// mc.whitelist request [name] [mode ...]: posts a request with approve/deny buttons to the status channel.
+    /// the name defaults to the linked minecraft account, the modes to the current one.
+    async fn whitelist_request(&self, ctx: &Context, msg: &Message, args: &str) {
+        let reply = |content: String| async move {
+            if let Err(e) = msg.reply(&ctx.http, content).await {
+                eprintln!("Error sending message: {:?}", e);
+                metrics::discord_send_error();
+            }
+        };
+        let mut args = args.split_whitespace();
+        let name = match args.next() {
+            Some(name) => name.to_owned(),
+            None => {
+                let linked = match &self.db {
+                    Some(db) => db
+                        .lock()
+                        .await
+                        .linked_account(msg.author.id.0)
+                        .ok()
+                        .flatten(),
+                    None => None,
+                };
+                match linked {
+                    Some((name, _uuid)) => name,
+                    None => {
+                        reply("usage: mc.whitelist request <minecraft name> [mode ...] (the name can be left out if you used mc.link)".to_owned()).await;
+                        return;
+                    }
+                }
+            }
+        };
+        if !minecraft_manager::is_valid_player_name(&name) {
+            reply(format!("'{name}' isn't a valid minecraft name")).await;
+            return;
+        }
+        let mut modes: Vec<String> = args.map(|m| m.to_owned()).collect();
+        if modes.is_empty() {
+            modes.push(self.start_as.lock().await.clone());
+        }
+        let mode_names = {
+            let server_configs = self.server_configs.lock().await;
+            let mut mode_names = vec![];
+            for mode in &modes {
+                match server_configs.get(mode) {
+                    Some((display_name, _cfg)) => {
+                        mode_names.push(format!("{display_name} ({mode})"))
+                    }
+                    None => {
+                        reply(format!("there is no mode with id '{mode}'")).await;
+                        return;
+                    }
+                }
+            }
+            mode_names.join(", ")
+        };
+        let request = whitelist::WhitelistRequest {
+            discord_id: msg.author.id.0,
+            name,
+            modes,
+        };
+        let (Some(approve_id), Some(deny_id)) = (request.custom_id(true), request.custom_id(false))
+        else {
+            reply("too many modes, request fewer at once".to_owned()).await;
+            return;
+        };
+        let posted = ChannelId(self.status_channel_id)
+            .send_message(&ctx.http, |m| {
+                m.content(format!(
+                    "<@{}> wants {} to be whitelisted on {mode_names}.",
+                    request.discord_id, request.name
+                ))
+                .allowed_mentions(|m| m.empty_parse())
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_button(|b| {
+                            b.custom_id(approve_id)
+                                .label("Approve")
+                                .style(ButtonStyle::Success)
+                        })
+                        .create_button(|b| {
+                            b.custom_id(deny_id)
+                                .label("Deny")
+                                .style(ButtonStyle::Danger)
+                        })
+                    })
+                })
+            })
+            .await;
+        match posted {
+            Ok(_) => reply("your request was sent to the admins.".to_owned()).await,
+            Err(e) => {
+                eprintln!("Error sending message: {:?}", e);
+                metrics::discord_send_error();
+                reply("couldn't post your request, try again later.".to_owned()).await;
+            }
+        }
+    }
+    /// the approve/deny buttons on whitelist requests, which only admins can use
+    async fn whitelist_button(
+        &self,
+        ctx: &Context,
+        component: &MessageComponentInteraction,
+        approve: bool,
+        request: whitelist::WhitelistRequest,
+    ) {
+        // without a `whitelist` rule in the permissions file, admins can approve requests
+        let has_rule = request
+            .modes
+            .iter()
+            .any(|mode| self.permissions.rule("whitelist", Some(mode)).is_some());
+        let is_allowed = if has_rule {
+            let role_ids: Vec<_> = match &component.member {
+                Some(member) => member.roles.iter().map(|r| r.0).collect(),
+                None => vec![],
+            };
+            let mut is_allowed = true;
+            for mode in &request.modes {
+                is_allowed &= self
+                    .is_allowed("whitelist", Some(mode), component.user.id.0, &role_ids)
+                    .await;
+            }
+            is_allowed
+        } else {
+            component
+                .member
+                .as_ref()
+                .and_then(|m| m.permissions)
+                .is_some_and(|p| p.administrator() || p.manage_guild())
+        };
+        let action = format!(
+            "whitelist {} {} on {}",
+            if approve { "approve" } else { "deny" },
+            request.name,
+            request.modes.join(",")
+        );
+        if !is_allowed {
+            self.audit
+                .record(ctx, &component.user, &action, "denied (no permission)")
+                .await;
+            if let Err(e) = component
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| {
+                            d.content(
+                                "you don't have permission to approve or deny whitelist requests.",
+                            )
+                            .ephemeral(true)
+                        })
+                })
+                .await
+            {
+                eprintln!("Error sending message: {:?}", e);
+                metrics::discord_send_error();
+            }
+            return;
+        }
+        // looking up uuids can take longer than discord waits for a response
+        if let Err(e) = component
+            .create_interaction_response(&ctx.http, |r| {
+                r.kind(InteractionResponseType::DeferredUpdateMessage)
+            })
+            .await
+        {
+            eprintln!("Error sending message: {:?}", e);
+            metrics::discord_send_error();
+            return;
+        }
+        let result = if !approve {
+            format!("denied by {}.", component.user.tag())
+        } else if !minecraft_manager::is_valid_player_name(&request.name) {
+            format!("'{}' isn't a valid minecraft name.", request.name)
+        } else {
+            let mut result = format!("approved by {}:\n", component.user.tag());
+            for mode in &request.modes {
+                let Some(directory) = self
+                    .server_configs
+                    .lock()
+                    .await
+                    .get(mode)
+                    .map(|(_name, cfg)| cfg.directory.clone())
+                else {
+                    result.push_str(&format!("- {mode}: this mode doesn't exist anymore\n"));
+                    continue;
+                };
+                let running = self.running_mode.lock().await.as_deref() == Some(mode.as_str());
+                let sent = running
+                    && match self.task_sender.lock().await.as_ref() {
+                        Some(task_sender) => task_sender
+                            .send_task(MinecraftServerTask::RunCommand(format!(
+                                "whitelist add {}",
+                                request.name
+                            )))
+                            .is_ok(),
+                        None => false,
+                    };
+                result.push_str(&if sent {
+                    format!("- {mode}: ran `whitelist add {}`\n", request.name)
+                } else {
+                    match whitelist::add_to_file(&directory, &request.name).await {
+                        Ok(true) => format!("- {mode}: added to whitelist.json\n"),
+                        Ok(false) => format!("- {mode}: already whitelisted\n"),
+                        Err(e) => format!("- {mode}: failed, {e}\n"),
+                    }
+                });
+            }
+            result
+        };
+        self.audit
+            .record(ctx, &component.user, &action, &result)
+            .await;
+        let content = format!("{}\n{result}", component.message.content);
+        if let Err(e) = component
+            .edit_original_interaction_response(&ctx.http, |r| r.content(content).components(|c| c))
+            .await
+        {
+            eprintln!("Error sending message: {:?}", e);
+            metrics::discord_send_error();
+        }
+        // edits don't ping, so let the requester know with a new message
+        if let Err(e) = ChannelId(self.status_channel_id)
+            .send_message(&ctx.http, |m| {
+                m.content(format!(
+                    "<@{}> your whitelist request for {} was {}.",
+                    request.discord_id,
+                    request.name,
+                    if approve { "approved" } else { "denied" }
+                ))
+                .allowed_mentions(|m| m.users([UserId(request.discord_id)]))
+            })
+            .await
+        {
+            eprintln!("Error sending message: {:?}", e);
+            metrics::discord_send_error();
+        }
+    }
+    /// mc.install <mode> <type> <version>
+    async fn install(&self, ctx: &Context, msg: &Message, args: &str) {
+        let reply = |content: String| async move {
+            if let Err(e) = msg.reply(&ctx.http, content).await {
+                eprintln!("Error sending message: {:?}", e);
+                metrics::discord_send_error();
+            }
+        };
+        let args: Vec<_> = args.split_whitespace().collect();
+        let [id, project, version] = args[..] else {
+            reply("usage: mc.install <mode> <vanilla|paper|folia|velocity|waterfall> <version|latest>".to_owned()).await;
+            return;
+        };
+        let directory = match self.server_configs.lock().await.get(id) {
+            Some((_name, cfg)) => cfg.directory.clone(),
+            None => {
+                reply(format!("there is no mode with id '{id}'")).await;
+                return;
+            }
+        };
+        reply(format!("downloading {project} {version} for {id}...")).await;
+        match self
+            .install_apis
+            .install(project, version, &directory)
+            .await
+        {
+            Ok(jar) => {
+                self.audit
+                    .record(ctx, &msg.author, &msg.content, &format!("installed {jar}"))
+                    .await;
+                // the log format and commands depend on the type, so it has to match the jar.
+                // custom types are left as they are, they have their own parser.
+                let mut new_type = None;
+                if let Some((_name, cfg)) = self.server_configs.lock().await.get_mut(id) {
+                    cfg.executable = jar.clone();
+                    if let Some((server_type, type_name)) = install::server_type(project) {
+                        if !matches!(cfg.server_type, MinecraftServerType::Custom { .. }) {
+                            cfg.server_type = server_type;
+                            new_type = Some(type_name);
+                        }
+                    }
+                }
+                let saved =
+                    servers_file::set_key(&self.servers_file, id, "exec", &jar).and_then(|()| {
+                        match new_type {
+                            Some(type_name) => {
+                                servers_file::set_key(&self.servers_file, id, "type", type_name)
+                            }
+                            None => Ok(()),
+                        }
+                    });
+                if let Err(e) = saved {
+                    eprintln!("Couldn't update servers file: {e}");
+                    reply(format!(
+                        "installed {jar}, but couldn't update the servers file ({e}). the change will be lost when the bot restarts!"
+                    ))
+                    .await;
+                } else {
+                    reply(format!(
+                        "installed {jar}, it will be used the next time {id} is started."
+                    ))
+                    .await;
+                }
+            }
+            Err(e) => {
+                eprintln!("Install failed: {e:?}");
+                self.audit
+                    .record(ctx, &msg.author, &msg.content, &format!("failed: {e}"))
+                    .await;
+                reply(format!("install failed: {e}")).await;
+            }
+        }
+    }
+    /// mc.create <id> <display name> <template> [seed=...] [gamemode=...] [difficulty=...] [eula=true]
+    async fn create(&self, ctx: &Context, msg: &Message, args: &str) {
+        let reply = |content: String| async move {
+            if let Err(e) = msg.reply(&ctx.http, content).await {
+                eprintln!("Error sending message: {:?}", e);
+                metrics::discord_send_error();
+            }
+        };
+        let Some((templates_dir, create_dir)) = &self.create_dirs else {
+            reply(
+                "mc.create is disabled (set mcdcbot_templates and mcdcbot_create_dir to enable it)"
+                    .to_owned(),
+            )
+            .await;
+            return;
+        };
+        let mut options = create::CreateOptions::default();
+        let mut words = vec![];
+        for arg in args.split_whitespace() {
+            match options.parse_arg(arg) {
+                Ok(true) => (),
+                Ok(false) => words.push(arg),
+                Err(e) => {
+                    reply(format!("{e}")).await;
+                    return;
+                }
+            }
+        }
+        let (id, display_name, template) = match words[..] {
+            [id, ref display_name @ .., template] if !display_name.is_empty() => {
+                (id, display_name.join(" "), template)
+            }
+            _ => {
+                reply("usage: mc.create <id> <display name> <template> [seed=...] [gamemode=...] [difficulty=...] [eula=true]".to_owned()).await;
+                return;
+            }
+        };
+        if self.server_configs.lock().await.contains_key(id) {
+            reply(format!("there already is a mode with id '{id}'")).await;
+            return;
+        }
+        // copying the template can take a while, so this doesn't lock server_configs
+        // and doesn't block the other tasks
+        let accept_eula = options.accept_eula;
+        let created = {
+            let (templates_dir, create_dir) = (templates_dir.clone(), create_dir.clone());
+            let (id, template) = (id.to_owned(), template.to_owned());
+            tokio::task::spawn_blocking(move || {
+                create::create(&templates_dir, &create_dir, &id, &template, &options)
+            })
+            .await
+        };
+        let created = match created {
+            Ok(created) => created,
+            Err(e) => {
+                eprintln!("Create failed: {e:?}");
+                self.audit
+                    .record(ctx, &msg.author, &msg.content, "failed: internal error")
+                    .await;
+                reply("couldn't create server: internal error (see the bot's log)".to_owned())
+                    .await;
+                return;
+            }
+        };
+        match created {
+            Ok((lines, settings)) => {
+                // another mode with this id could have been added in the meantime
+                let inserted = match self.server_configs.lock().await.entry(id.to_owned()) {
+                    std::collections::hash_map::Entry::Occupied(_) => false,
+                    std::collections::hash_map::Entry::Vacant(entry) => {
+                        entry.insert((display_name.clone(), settings));
+                        true
+                    }
+                };
+                if !inserted {
+                    reply(format!("there already is a mode with id '{id}'")).await;
+                    return;
+                }
+                self.audit
+                    .record(ctx, &msg.author, &msg.content, &format!("created {id}"))
+                    .await;
+                if let Err(e) =
+                    servers_file::append_server(&self.servers_file, id, &display_name, &lines)
+                {
+                    eprintln!("Couldn't update servers file: {e}");
+                    reply(format!("couldn't add the new server to the servers file ({e}), it will be lost when the bot restarts!")).await;
+                }
+                metrics::add_mode(id);
+                reply(format!(
+                    "created {display_name} from template {template}, use `mc.setmode {id}` to select it.{}",
+                    if accept_eula {
+                        ""
+                    } else {
+                        "\nthe minecraft EULA was not accepted, so the server won't start until it is (add eula=true to accept it)."
+                    }
+                ))
+                .await;
+            }
+            Err(e) => {
+                eprintln!("Create failed: {e:?}");
+                self.audit
+                    .record(ctx, &msg.author, &msg.content, &format!("failed: {e}"))
+                    .await;
+                reply(format!("couldn't create server: {e}")).await;
+            }
+        }
+    }
+    /// proxies don't have a tellraw command, so nothing can be shown to the players
+    async fn running_is_proxy(&self) -> bool {
+        let Some(mode) = self.running_mode.lock().await.clone() else {
+            return false;
+        };
+        self.server_configs
+            .lock()
+            .await
+            .get(&mode)
+            .is_some_and(|(_name, cfg)| cfg.server_type.is_proxy())
+    }
     async fn get_my_ip(&self) -> Option<String> {
         if let Ok(curl) = std::process::Command::new("curl")
             .arg("https://ipinfo.io/ip")
@@ -505,6 +2083,63 @@ impl Handler {
     }
 }
 
+/// minecraft chat as an embed from the bot (if there is no chat webhook)
+async fn send_chat_embed(
+    ctx: &Context,
+    chat_channel_id: u64,
+    author: &str,
+    message: &discord_text::DiscordText,
+) -> serenity::Result<()> {
+    ChannelId(chat_channel_id)
+        .send_message(&ctx.http, |m| {
+            // mentions in embeds don't notify anyone
+            if !message.pings.is_empty() {
+                m.content(
+                    message
+                        .pings
+                        .iter()
+                        .map(|id| format!("<@{id}> "))
+                        .collect::<String>(),
+                );
+            }
+            m.embed(|e| e.author(|a| a.name(author)).description(&message.text))
+                .allowed_mentions(|m| {
+                    m.empty_parse()
+                        .users(message.pings.iter().map(|id| UserId(*id)))
+                })
+        })
+        .await?;
+    Ok(())
+}
+
+async fn send_lag_warning(ctx: &Context, status_channel_id: u64, warning: String) {
+    if let Err(e) = ChannelId(status_channel_id)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(Colour::from_rgb(200, 70, 00))
+                    .title("server is lagging")
+                    .description(warning)
+            })
+        })
+        .await
+    {
+        eprintln!("Couldn't send message: {e:?}");
+        metrics::discord_send_error();
+    }
+}
+
+fn format_resource_usage(usage: &ResourceUsage) -> String {
+    format!(
+        "{:.2} GiB RAM, {} CPU, {} threads",
+        usage.rss_bytes as f64 / (1024.0 * 1024.0 * 1024.0),
+        match usage.cpu_percent {
+            Some(cpu) => format!("{cpu:.0}%"),
+            None => "?%".to_owned(),
+        },
+        usage.threads
+    )
+}
+
 // async fn set_status_to_current_time(ctx: Arc<Context>) {
 // let current_time = Utc::now();
 // let formatted_time = current_time.to_rfc2822();
@@ -532,6 +2167,24 @@ async fn main() {
         Ok(v) => v.trim().to_owned(),
         Err(_) => String::new(),
     };
+    let create_dirs = match (
+        std::env::var("mcdcbot_templates"),
+        std::env::var("mcdcbot_create_dir"),
+    ) {
+        (Ok(templates), Ok(create_dir)) => Some((templates, create_dir)),
+        _ => None,
+    };
+    let db_path = match std::env::var("mcdcbot_db") {
+        Ok(v) => std::path::PathBuf::from(v),
+        Err(_) => std::path::Path::new(&servers_file).with_file_name("mcdcbot.sqlite"),
+    };
+    let db = match db::Db::open(&db_path) {
+        Ok(db) => Some(Arc::new(Mutex::new(db))),
+        Err(e) => {
+            eprintln!("Couldn't open database {}: {e}", db_path.display());
+            None
+        }
+    };
     let mut server_configs = HashMap::new();
     match std::fs::read_to_string(&servers_file) {
         Ok(v) => {
@@ -562,10 +2215,15 @@ async fn main() {
             "Couldn't load file provided through mcdcbot_servers env var ({servers_file}): {e}"
         ),
     }
-    let intents = GatewayIntents::GUILD_MESSAGES
+    let mut intents = GatewayIntents::GUILD_MESSAGES
         // | GatewayIntents::DIRECT_MESSAGES
-        | GatewayIntents::GUILDS
-        | GatewayIntents::MESSAGE_CONTENT;
+        | GatewayIntents::GUILDS;
+    // the mc. text commands and chat from discord need message content, slash commands don't
+    let message_content =
+        std::env::var("mcdcbot_message_content").map_or(true, |v| v.trim() != "false");
+    if message_content {
+        intents |= GatewayIntents::MESSAGE_CONTENT;
+    }
     eprintln!(" | - - STARTING - - |");
     eprintln!(
         " | token: {} ... {}",
@@ -575,6 +2233,10 @@ async fn main() {
     eprintln!(" | report channel id: {report_channel_id}");
     eprintln!(" | status channel id: {status_channel_id}");
     eprintln!(" | chat   channel id: {chat_channel_id}");
+    eprintln!(" | database: {}", db_path.display());
+    if !message_content {
+        eprintln!(" | message content intent disabled: only slash commands work, chat is only sent from minecraft to discord");
+    }
     eprintln!(" | server configs:");
     for (id, (name, cfg)) in server_configs.iter() {
         eprintln!(" | | {id} - \"{name}\" - {cfg}");
@@ -587,18 +2249,57 @@ async fn main() {
             " (WARN: doesn't exist - use mcdcbot_server_default env var to change)"
         }
     );
+    let audit = audit::AuditLog::from_env(
+        std::path::Path::new(&servers_file)
+            .with_file_name("mcdcbot_audit.log")
+            .to_string_lossy()
+            .into_owned(),
+        report_channel_id,
+    );
+    eprintln!(" | audit log: {}", audit.path());
+    let permissions = permissions::Permissions::from_env();
+    if permissions.is_empty() {
+        eprintln!(
+            " | permissions: everyone can use all commands (use mcdcbot_permissions to change)"
+        );
+    } else {
+        eprintln!(" | permissions: loaded from mcdcbot_permissions");
+    }
+    for id in server_configs.keys() {
+        metrics::add_mode(id);
+    }
+    match std::env::var("mcdcbot_metrics_addr") {
+        Ok(addr) => match metrics::serve(&addr) {
+            Ok(()) => eprintln!(" | metrics: http://{addr}/metrics"),
+            Err(e) => eprintln!(" | metrics: couldn't listen on {addr}: {e}"),
+        },
+        Err(_) => eprintln!(" | metrics: disabled (set mcdcbot_metrics_addr to enable)"),
+    }
     let mut client = Client::builder(&token, intents)
         .event_handler(Handler {
             is_running: Arc::new(AtomicBool::new(false)),
             should_run: AtomicBool::new(false),
             start_as: Arc::new(Mutex::new(default_server_config)),
+            running_mode: Arc::new(Mutex::new(None)),
+            refresh_status: Arc::new(AtomicBool::new(false)),
             task_sender: Arc::new(Mutex::new(None)),
             bot_loop: Arc::new(Mutex::new(None)),
+            players_online: Arc::new(Mutex::new(HashMap::new())),
+            resources: Arc::new(Mutex::new(None)),
             my_ip: Arc::new(Mutex::new(format!("(???)"))),
             chat_channel_id,
             status_channel_id,
             report_channel_id,
-            server_configs: Arc::new(server_configs),
+            server_configs: Arc::new(Mutex::new(server_configs)),
+            servers_file,
+            install_apis: install::InstallApis::from_env(),
+            create_dirs,
+            db,
+            chat_webhook: webhook::ChatWebhook::from_env().map(Arc::new),
+            chat_pings: Arc::new(discord_text::pings_from_env()),
+            pending_links: Mutex::new(link::PendingLinks::default()),
+            permissions,
+            audit,
         })
         .await
         .expect("Error creating client");
@@ -0,0 +1,216 @@
+//! Metrics in the prometheus text format, served over http if `mcdcbot_metrics_addr` is set
+//! (for example `127.0.0.1:9100`). The endpoint has no authentication, so it should only listen locally.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{BufRead, BufReader, Write as _},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use minecraft_manager::events::MinecraftServerEventType;
+
+static METRICS: Metrics = Metrics {
+    modes: Mutex::new(BTreeMap::new()),
+    events: Mutex::new(BTreeMap::new()),
+    chat_to_discord: AtomicU64::new(0),
+    chat_to_minecraft: AtomicU64::new(0),
+    discord_send_errors: AtomicU64::new(0),
+};
+
+struct Metrics {
+    modes: Mutex<BTreeMap<String, ModeMetrics>>,
+    /// number of events from the server, by event type
+    events: Mutex<BTreeMap<&'static str, u64>>,
+    chat_to_discord: AtomicU64,
+    chat_to_minecraft: AtomicU64,
+    discord_send_errors: AtomicU64,
+}
+
+#[derive(Default)]
+struct ModeMetrics {
+    /// when the server was started, None if it isn't running
+    started: Option<Instant>,
+    players_online: usize,
+    rss_bytes: Option<u64>,
+}
+
+/// adds a mode so it shows up (as down) before it is started for the first time
+pub fn add_mode(mode: &str) {
+    METRICS
+        .modes
+        .lock()
+        .unwrap()
+        .entry(mode.to_owned())
+        .or_default();
+}
+pub fn server_started(mode: &str) {
+    let mut modes = METRICS.modes.lock().unwrap();
+    let m = modes.entry(mode.to_owned()).or_default();
+    m.started = Some(Instant::now());
+    m.players_online = 0;
+    m.rss_bytes = None;
+}
+pub fn server_stopped(mode: &str) {
+    let mut modes = METRICS.modes.lock().unwrap();
+    let m = modes.entry(mode.to_owned()).or_default();
+    m.started = None;
+    m.players_online = 0;
+    m.rss_bytes = None;
+}
+pub fn players_online(mode: &str, players: usize) {
+    METRICS
+        .modes
+        .lock()
+        .unwrap()
+        .entry(mode.to_owned())
+        .or_default()
+        .players_online = players;
+}
+pub fn process_memory(mode: &str, rss_bytes: u64) {
+    METRICS
+        .modes
+        .lock()
+        .unwrap()
+        .entry(mode.to_owned())
+        .or_default()
+        .rss_bytes = Some(rss_bytes);
+}
+pub fn event(event: &MinecraftServerEventType) {
+    let name = match event {
+        MinecraftServerEventType::Warning(_) => "warning",
+        MinecraftServerEventType::JoinLeave(_) => "join_leave",
+        MinecraftServerEventType::ChatMessage(_) => "chat_message",
+        MinecraftServerEventType::BackendSwitch(_) => "backend_switch",
+        MinecraftServerEventType::PlayerUuid(_) => "player_uuid",
+        MinecraftServerEventType::EulaNotAccepted => "eula_not_accepted",
+        MinecraftServerEventType::StartupFailure(_) => "startup_failure",
+        MinecraftServerEventType::Lag(_) => "lag",
+        MinecraftServerEventType::Tps(_) => "tps",
+        MinecraftServerEventType::Mspt(_) => "mspt",
+    };
+    *METRICS.events.lock().unwrap().entry(name).or_default() += 1;
+}
+pub fn chat_to_discord() {
+    METRICS.chat_to_discord.fetch_add(1, Ordering::Relaxed);
+}
+pub fn chat_to_minecraft() {
+    METRICS.chat_to_minecraft.fetch_add(1, Ordering::Relaxed);
+}
+pub fn discord_send_error() {
+    METRICS.discord_send_errors.fetch_add(1, Ordering::Relaxed);
+}
+
+/// starts a thread which serves the metrics on `addr`
+pub fn serve(addr: &str) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(e) = handle_connection(stream) {
+                        eprintln!("Metrics: couldn't answer request: {e}");
+                    }
+                }
+                Err(e) => eprintln!("Metrics: couldn't accept connection: {e}"),
+            }
+        }
+    });
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    // `GET /metrics HTTP/1.1`
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // skip the headers
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+    }
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics" | "/")) => ("200 OK", render()),
+        (Some("GET"), _) => ("404 Not Found", "not found, try /metrics\n".to_owned()),
+        _ => ("405 Method Not Allowed", String::new()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn render() -> String {
+    let mut out = String::new();
+    {
+        let modes = METRICS.modes.lock().unwrap();
+        let mut gauge = |name: &str, help: &str, value: &dyn Fn(&ModeMetrics) -> Option<f64>| {
+            _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} gauge");
+            for (mode, m) in modes.iter() {
+                if let Some(v) = value(m) {
+                    _ = writeln!(out, "{name}{{mode=\"{}\"}} {v}", escape_label(mode));
+                }
+            }
+        };
+        gauge(
+            "mcdcbot_server_up",
+            "1 if the server is running, 0 otherwise.",
+            &|m| Some(if m.started.is_some() { 1.0 } else { 0.0 }),
+        );
+        gauge(
+            "mcdcbot_server_uptime_seconds",
+            "Time since the server was started.",
+            &|m| m.started.map(|t| t.elapsed().as_secs_f64()),
+        );
+        gauge(
+            "mcdcbot_players_online",
+            "Number of players on the server.",
+            &|m| Some(m.players_online as f64),
+        );
+        gauge(
+            "mcdcbot_server_memory_bytes",
+            "Resident memory of the server process.",
+            &|m| m.rss_bytes.map(|v| v as f64),
+        );
+    }
+    _ = writeln!(
+        out,
+        "# HELP mcdcbot_server_events_total Events parsed from the server's output.\n# TYPE mcdcbot_server_events_total counter"
+    );
+    for (name, count) in METRICS.events.lock().unwrap().iter() {
+        _ = writeln!(
+            out,
+            "mcdcbot_server_events_total{{type=\"{name}\"}} {count}"
+        );
+    }
+    _ = writeln!(
+        out,
+        "# HELP mcdcbot_chat_messages_relayed_total Chat messages relayed between minecraft and discord.\n# TYPE mcdcbot_chat_messages_relayed_total counter\nmcdcbot_chat_messages_relayed_total{{direction=\"to_discord\"}} {}\nmcdcbot_chat_messages_relayed_total{{direction=\"to_minecraft\"}} {}",
+        METRICS.chat_to_discord.load(Ordering::Relaxed),
+        METRICS.chat_to_minecraft.load(Ordering::Relaxed),
+    );
+    _ = writeln!(
+        out,
+        "# HELP mcdcbot_discord_send_errors_total Messages which couldn't be sent to discord.\n# TYPE mcdcbot_discord_send_errors_total counter\nmcdcbot_discord_send_errors_total {}",
+        METRICS.discord_send_errors.load(Ordering::Relaxed),
+    );
+    out
+}
+
+/// escapes `\`, `"` and newlines in a label value
+fn escape_label(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
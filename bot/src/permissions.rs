@@ -0,0 +1,118 @@
+//! Who can use which control commands, from the file in `mcdcbot_permissions`.
+//!
+//! Each line is `<command> [mode] = <who> <who> ...`, where who is `everyone`, `role:<role id>`,
+//! `user:<user id>` or `player:<minecraft name>` (a discord user linked to that player with mc.link).
+//! `*` as the command matches all commands which don't have their own rule.
+//! Commands without any matching rule can be used by everyone. Lines starting with `#` are ignored.
+
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub enum Allowed {
+    Everyone,
+    Role(u64),
+    User(u64),
+    Player(String),
+}
+
+#[derive(Default)]
+pub struct Permissions {
+    /// (command, mode) -> who is allowed to use it. mode is None for rules which apply to all modes.
+    rules: HashMap<(String, Option<String>), Vec<Allowed>>,
+}
+
+impl Permissions {
+    /// no rules if `mcdcbot_permissions` isn't set. panics if the file can't be read or is invalid.
+    pub fn from_env() -> Self {
+        let Ok(path) = std::env::var("mcdcbot_permissions") else {
+            return Self::default();
+        };
+        let file = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+            panic!("Couldn't load file provided through mcdcbot_permissions env var ({path}): {e}")
+        });
+        match Self::parse(&file) {
+            Ok(v) => v,
+            Err(e) => panic!("Invalid permissions file ({path}): {e}"),
+        }
+    }
+
+    pub fn parse(file: &str) -> Result<Self, String> {
+        let mut rules = HashMap::new();
+        for line in file.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((command, allowed)) = line.split_once('=') else {
+                return Err(format!(
+                    "line '{line}' must look like <command> [mode] = <who> ..."
+                ));
+            };
+            let key = match command.split_whitespace().collect::<Vec<_>>()[..] {
+                [command] => (command.to_owned(), None),
+                [command, mode] => (command.to_owned(), Some(mode.to_owned())),
+                _ => {
+                    return Err(format!(
+                        "line '{line}' must look like <command> [mode] = <who> ..."
+                    ))
+                }
+            };
+            let allowed = allowed
+                .split_whitespace()
+                .map(|who| match who.split_once(':') {
+                    None if who == "everyone" => Ok(Allowed::Everyone),
+                    Some(("role", id)) => id.parse().map(Allowed::Role).map_err(|_| who),
+                    Some(("user", id)) => id.parse().map(Allowed::User).map_err(|_| who),
+                    Some(("player", name)) if !name.is_empty() => {
+                        Ok(Allowed::Player(name.to_owned()))
+                    }
+                    _ => Err(who),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|who| {
+                    format!("'{who}' must be everyone, role:<id>, user:<id> or player:<name>")
+                })?;
+            rules.insert(key, allowed);
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// the most specific rule for the command in the mode: `command mode`, `command`, `* mode`, `*`.
+    /// None if there is no rule, which means everyone may use the command.
+    pub fn rule(&self, command: &str, mode: Option<&str>) -> Option<&[Allowed]> {
+        let mode = mode.map(|m| m.to_owned());
+        [
+            (command.to_owned(), mode.clone()),
+            (command.to_owned(), None),
+            ("*".to_owned(), mode),
+            ("*".to_owned(), None),
+        ]
+        .iter()
+        .find_map(|key| self.rules.get(key))
+        .map(|v| v.as_slice())
+    }
+
+    /// `linked_player` is the minecraft account linked to the user, for `player:` rules
+    pub fn allows(
+        &self,
+        command: &str,
+        mode: Option<&str>,
+        user_id: u64,
+        role_ids: &[u64],
+        linked_player: Option<&str>,
+    ) -> bool {
+        let Some(rule) = self.rule(command, mode) else {
+            return true;
+        };
+        rule.iter().any(|allowed| match allowed {
+            Allowed::Everyone => true,
+            Allowed::Role(id) => role_ids.contains(id),
+            Allowed::User(id) => *id == user_id,
+            Allowed::Player(name) => linked_player.is_some_and(|p| p.eq_ignore_ascii_case(name)),
+        })
+    }
+}
@@ -0,0 +1,64 @@
+//! Editing the servers file (mcdcbot_servers) while the bot is running,
+//! so changes made through discord commands survive a restart.
+
+use std::io;
+
+/// sets `key=value` in the config block of the server with the given id.
+/// if the key already exists, its line is replaced, otherwise a new line is added at the end of the block.
+pub fn set_key(path: &str, id: &str, key: &str, value: &str) -> io::Result<()> {
+    let file = std::fs::read_to_string(path)?;
+    let mut out = Vec::new();
+    // Some(true) while inside the block of the server we're looking for
+    let mut in_block = None;
+    let mut done = false;
+    for line in file.lines() {
+        if line.trim().is_empty() {
+            if in_block == Some(true) && !done {
+                out.push(format!("{key}={value}"));
+                done = true;
+            }
+            in_block = None;
+        } else if in_block.is_none() {
+            // header line
+            in_block = Some(line.split_once(' ').map(|v| v.0) == Some(id));
+        } else if in_block == Some(true) && !done && line.split_once('=').map(|v| v.0) == Some(key)
+        {
+            out.push(format!("{key}={value}"));
+            done = true;
+            continue;
+        }
+        out.push(line.to_owned());
+    }
+    if in_block == Some(true) && !done {
+        out.push(format!("{key}={value}"));
+        done = true;
+    }
+    if !done {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no server with id '{id}' in {path}"),
+        ));
+    }
+    let mut out = out.join("\n");
+    out.push('\n');
+    std::fs::write(path, out)
+}
+
+/// adds a new server block (header line and config lines) to the end of the file.
+pub fn append_server(path: &str, id: &str, display_name: &str, lines: &[String]) -> io::Result<()> {
+    let mut file = std::fs::read_to_string(path)?;
+    if !file.is_empty() {
+        if !file.ends_with('\n') {
+            file.push('\n');
+        }
+        if !file.ends_with("\n\n") {
+            file.push('\n');
+        }
+    }
+    file.push_str(&format!("{id} {display_name}\n"));
+    for line in lines {
+        file.push_str(line);
+        file.push('\n');
+    }
+    std::fs::write(path, file)
+}
@@ -0,0 +1,137 @@
+//! The `/mc` slash command, which does the same as the `mc.` text commands for controlling the
+//! server, but doesn't need the message content intent and has autocomplete.
+
+use serenity::{
+    builder::CreateApplicationCommandOption,
+    model::{
+        application::{
+            command::CommandOptionType, interaction::application_command::CommandDataOption,
+        },
+        id::ChannelId,
+    },
+    prelude::Context,
+};
+
+/// registers `/mc` in the guild of the status channel (guild commands are available right away,
+/// unlike global ones)
+pub async fn register(ctx: &Context, status_channel_id: u64) -> serenity::Result<()> {
+    let Some(channel) = ChannelId(status_channel_id).to_channel(ctx).await?.guild() else {
+        eprintln!("The status channel isn't in a guild, slash commands won't be available.");
+        return Ok(());
+    };
+    channel
+        .guild_id
+        .set_application_commands(&ctx.http, |commands| {
+            commands.create_application_command(|c| {
+                c.name("mc")
+                    .description("control the minecraft server")
+                    .create_option(|o| {
+                        o.name("start")
+                            .description("start the server in the current mode")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+                    .create_option(|o| {
+                        o.name("stop")
+                            .description("stop the server")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(force_option)
+                    })
+                    .create_option(|o| {
+                        o.name("mode")
+                            .description(
+                                "select the mode which is used the next time the server starts",
+                            )
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|o| {
+                                o.name("id")
+                                    .description("the mode's id")
+                                    .kind(CommandOptionType::String)
+                                    .required(true)
+                                    .set_autocomplete(true)
+                            })
+                    })
+                    .create_option(|o| {
+                        o.name("run")
+                            .description("run a command in the server's console")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|o| {
+                                o.name("command")
+                                    .description("the command, without the /")
+                                    .kind(CommandOptionType::String)
+                                    .required(true)
+                                    .set_autocomplete(true)
+                            })
+                    })
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+fn force_option(o: &mut CreateApplicationCommandOption) -> &mut CreateApplicationCommandOption {
+    o.name("force")
+        .description("don't ask for confirmation if players are online")
+        .kind(CommandOptionType::Boolean)
+}
+
+pub struct Subcommand<'a> {
+    pub name: &'a str,
+    /// the (only) string argument
+    pub arg: Option<&'a str>,
+    /// whether `arg` is currently being autocompleted
+    pub arg_focused: bool,
+    pub force: bool,
+}
+
+pub fn subcommand(options: &[CommandDataOption]) -> Option<Subcommand<'_>> {
+    let subcommand = options.first()?;
+    let arg = subcommand
+        .options
+        .iter()
+        .find_map(|o| Some((o.value.as_ref()?.as_str()?, o.focused)));
+    let force = subcommand
+        .options
+        .iter()
+        .any(|o| o.name == "force" && o.value.as_ref().and_then(|v| v.as_bool()) == Some(true));
+    Some(Subcommand {
+        name: subcommand.name.as_str(),
+        arg: arg.map(|(value, _focused)| value),
+        arg_focused: arg.is_some_and(|(_value, focused)| focused),
+        force,
+    })
+}
+
+/// the `mc.` text command which does the same as the slash command
+pub fn text_command(subcommand: &str, arg: Option<&str>) -> Option<String> {
+    Some(match (subcommand, arg) {
+        ("start", _) => "mc.start".to_owned(),
+        ("stop", _) => "mc.stop".to_owned(),
+        ("mode", Some(id)) => format!("mc.setmode {id}"),
+        ("run", Some(command)) => format!("mc.run {command}"),
+        _ => return None,
+    })
+}
+
+/// autocomplete for `/mc run`: completes the last word with the names of online players.
+/// returns (name, value) choices.
+pub fn complete_player_names<'a>(
+    command: &str,
+    players: impl Iterator<Item = &'a String>,
+) -> Vec<(String, String)> {
+    // the first word is the command itself
+    let Some((start, last_word)) = command.rsplit_once(' ') else {
+        return vec![];
+    };
+    let start = format!("{start} ");
+    let last_word = last_word.to_lowercase();
+    let mut choices: Vec<_> = players
+        .filter(|player| player.to_lowercase().starts_with(&last_word))
+        .map(|player| format!("{start}{player}"))
+        // discord's limit for choices
+        .filter(|choice| choice.chars().count() <= 100)
+        .map(|choice| (choice.clone(), choice))
+        .collect();
+    choices.sort();
+    choices.truncate(25);
+    choices
+}
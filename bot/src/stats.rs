@@ -0,0 +1,180 @@
+//! Leaderboards (mc.top) from the statistics minecraft saves in `<world>/stats/<uuid>.json`.
+//! These files are only read, so this works while the server is stopped, too.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+#[derive(Clone, Copy)]
+pub enum Stat {
+    Deaths,
+    Playtime,
+    BlocksMined,
+    MobKills,
+    DistanceWalked,
+}
+
+impl Stat {
+    pub const NAMES: &'static str = "deaths, playtime, mined, kills, walked";
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "deaths" => Self::Deaths,
+            "playtime" => Self::Playtime,
+            "mined" | "blocks_mined" => Self::BlocksMined,
+            "kills" | "mob_kills" => Self::MobKills,
+            "walked" | "distance_walked" => Self::DistanceWalked,
+            _ => return None,
+        })
+    }
+    /// reads the value from the contents of a stats file
+    fn value(&self, stats: &Value) -> u64 {
+        let custom = |key: &str| {
+            stats["stats"]["minecraft:custom"][key]
+                .as_u64()
+                .unwrap_or(0)
+        };
+        match self {
+            Self::Deaths => custom("minecraft:deaths"),
+            // renamed in 1.17
+            Self::Playtime => custom("minecraft:play_time") + custom("minecraft:play_one_minute"),
+            Self::BlocksMined => stats["stats"]["minecraft:mined"]
+                .as_object()
+                .map(|mined| mined.values().filter_map(|v| v.as_u64()).sum())
+                .unwrap_or(0),
+            Self::MobKills => custom("minecraft:mob_kills"),
+            Self::DistanceWalked => {
+                custom("minecraft:walk_one_cm")
+                    + custom("minecraft:sprint_one_cm")
+                    + custom("minecraft:crouch_one_cm")
+            }
+        }
+    }
+    pub fn format(&self, value: u64) -> String {
+        match self {
+            Self::Deaths => format!("{value} deaths"),
+            // ticks
+            Self::Playtime => crate::db::format_duration(value as i64 / 20),
+            Self::BlocksMined => format!("{value} blocks"),
+            Self::MobKills => format!("{value} mobs"),
+            // centimeters
+            Self::DistanceWalked => format!("{:.1} km", value as f64 / 100_000.0),
+        }
+    }
+}
+impl Display for Stat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Deaths => "deaths",
+                Self::Playtime => "playtime",
+                Self::BlocksMined => "blocks mined",
+                Self::MobKills => "mob kills",
+                Self::DistanceWalked => "distance walked",
+            }
+        )
+    }
+}
+
+/// all players' values for the stat, highest first, as (player name, value).
+/// players whose name isn't in usercache.json are shown by uuid.
+pub fn leaderboard(directory: &str, stat: Stat) -> Result<Vec<(String, u64)>, StatsError> {
+    let directory = Path::new(directory);
+    let stats_dir = directory.join(level_name(directory)).join("stats");
+    let entries = match std::fs::read_dir(&stats_dir) {
+        Ok(v) => v,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(StatsError::NoStatsDirectory(stats_dir))
+        }
+        Err(e) => return Err(StatsError::Io(e)),
+    };
+    let names = usercache(directory);
+    let mut board = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        let Some(uuid) = path
+            .file_name()
+            .and_then(|v| v.to_str())
+            .and_then(|v| v.strip_suffix(".json"))
+        else {
+            continue;
+        };
+        let stats: Value = match serde_json::from_str(&std::fs::read_to_string(&path)?) {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Couldn't parse {}: {e}", path.display());
+                continue;
+            }
+        };
+        let value = stat.value(&stats);
+        if value > 0 {
+            let name = names.get(uuid).cloned().unwrap_or_else(|| uuid.to_owned());
+            board.push((name, value));
+        }
+    }
+    board.sort_by_key(|(_, value)| std::cmp::Reverse(*value));
+    Ok(board)
+}
+
+/// uuid -> name from the server's usercache.json (empty if it can't be read)
+pub fn usercache(directory: &Path) -> HashMap<String, String> {
+    let Ok(file) = std::fs::read_to_string(directory.join("usercache.json")) else {
+        return HashMap::new();
+    };
+    let Ok(Value::Array(entries)) = serde_json::from_str(&file) else {
+        return HashMap::new();
+    };
+    entries
+        .iter()
+        .filter_map(|entry| {
+            Some((
+                entry["uuid"].as_str()?.to_owned(),
+                entry["name"].as_str()?.to_owned(),
+            ))
+        })
+        .collect()
+}
+
+/// `level-name` from server.properties, `world` by default
+fn level_name(directory: &Path) -> String {
+    std::fs::read_to_string(directory.join("server.properties"))
+        .ok()
+        .and_then(|properties| {
+            properties.lines().find_map(|line| {
+                line.strip_prefix("level-name=")
+                    .map(|v| v.trim().to_owned())
+                    .filter(|v| !v.is_empty())
+            })
+        })
+        .unwrap_or_else(|| "world".to_owned())
+}
+
+#[derive(Debug)]
+pub enum StatsError {
+    NoStatsDirectory(PathBuf),
+    Io(io::Error),
+}
+impl Display for StatsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoStatsDirectory(dir) => write!(
+                f,
+                "{} doesn't exist (no one has played yet, or this server doesn't save stats)",
+                dir.display()
+            ),
+            Self::Io(e) => write!(f, "couldn't read stats: {e}"),
+        }
+    }
+}
+impl From<io::Error> for StatsError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
@@ -0,0 +1,85 @@
+//! Buttons on the status message: Stop / Restart / Refresh while the server is running,
+//! and Start with a mode select menu once it has stopped.
+
+use serenity::{builder::CreateComponents, model::application::component::ButtonStyle};
+
+pub enum StatusAction {
+    Start,
+    Stop,
+    Restart,
+    Refresh,
+    /// the mode which was selected in the menu
+    Mode(String),
+}
+
+impl StatusAction {
+    /// `values` are the selected values, for the select menu
+    pub fn parse(custom_id: &str, values: &[String]) -> Option<Self> {
+        Some(match custom_id {
+            "status:start" => Self::Start,
+            "status:stop" => Self::Stop,
+            "status:restart" => Self::Restart,
+            "status:refresh" => Self::Refresh,
+            "status:mode" => Self::Mode(values.first()?.clone()),
+            _ => return None,
+        })
+    }
+}
+
+/// the buttons for a running server
+pub fn running(c: &mut CreateComponents) -> &mut CreateComponents {
+    c.create_action_row(|row| {
+        row.create_button(|b| {
+            b.custom_id("status:stop")
+                .label("Stop")
+                .style(ButtonStyle::Danger)
+        })
+        .create_button(|b| {
+            b.custom_id("status:restart")
+                .label("Restart")
+                .style(ButtonStyle::Primary)
+        })
+        .create_button(|b| {
+            b.custom_id("status:refresh")
+                .label("Refresh")
+                .style(ButtonStyle::Secondary)
+        })
+    })
+}
+
+/// the start button and mode select menu for a stopped server.
+/// `modes` are (id, display name), `selected` is the mode which will be started.
+pub fn stopped<'a>(
+    c: &'a mut CreateComponents,
+    modes: &[(String, String)],
+    selected: &str,
+) -> &'a mut CreateComponents {
+    c.create_action_row(|row| {
+        row.create_button(|b| {
+            b.custom_id("status:start")
+                .label("Start")
+                .style(ButtonStyle::Success)
+        })
+    });
+    // discord allows at most 25 options
+    if !modes.is_empty() && modes.len() <= 25 {
+        c.create_action_row(|row| {
+            row.create_select_menu(|menu| {
+                menu.custom_id("status:mode")
+                    .placeholder("mode")
+                    .options(|options| {
+                        for (id, name) in modes {
+                            options.create_option(|o| {
+                                o.label(name)
+                                    .value(id)
+                                    .description(id)
+                                    .default_selection(id == selected)
+                            });
+                        }
+                        options
+                    })
+            })
+        });
+    }
+    c
+}
@@ -0,0 +1,146 @@
+//! Turning discord messages into text components for tellraw, for the chat bridge.
+//! All text goes through `TextComponent`, which escapes it, so messages can't inject json or commands.
+
+use minecraft_manager::chat::TextComponent;
+use serenity::{model::channel::Message, prelude::Context};
+
+/// longer messages are cut off (they can still be read on discord)
+const MAX_LENGTH: usize = 500;
+/// how much of the message which is being replied to is shown when hovering over `(replying to ...)`
+const MAX_REPLY_PREVIEW_LENGTH: usize = 100;
+
+/// `<Author> (replying to Steve) message https://link [attachment.png]`
+/// where the author has the color of their top discord role.
+/// if the author has linked their minecraft account, its name is shown instead of their discord name.
+pub async fn message_components(
+    ctx: &Context,
+    msg: &Message,
+    linked_name: Option<&str>,
+) -> Vec<TextComponent> {
+    let member = msg.member(ctx).await.ok();
+    let name = match linked_name {
+        Some(name) => name.to_owned(),
+        None => member
+            .as_ref()
+            .and_then(|m| m.nick.clone())
+            .unwrap_or_else(|| msg.author.name.clone()),
+    };
+    let mut author = TextComponent::new(strip_formatting_codes(&name))
+        .with_hover_text(format!("{} on discord", msg.author.tag()));
+    if let Some(colour) = member.as_ref().and_then(|m| m.colour(&ctx.cache)) {
+        author = author.with_color(format!("#{}", colour.hex()));
+    }
+    let mut components = vec![TextComponent::new("<"), author, TextComponent::new("> ")];
+    if let Some(reply) = &msg.referenced_message {
+        // minecraft chat sent by the bot has the player's name in the embed author
+        let reply_author = if reply.is_own(&ctx.cache) {
+            reply
+                .embeds
+                .first()
+                .and_then(|e| e.author.as_ref())
+                .map(|a| a.name.clone())
+                .unwrap_or_else(|| reply.author.name.clone())
+        } else {
+            reply.author.name.clone()
+        };
+        let reply_content = match reply.embeds.first().and_then(|e| e.description.as_ref()) {
+            Some(description) if reply.content.is_empty() => description.clone(),
+            _ => reply.content_safe(&ctx.cache),
+        };
+        components.push(
+            TextComponent::new(format!("(replying to {reply_author}) "))
+                .with_color("gray")
+                .with_italic()
+                .with_hover_text(truncate(
+                    &replace_custom_emoji(&reply_content),
+                    MAX_REPLY_PREVIEW_LENGTH,
+                )),
+        );
+    }
+    let content = truncate(
+        &replace_custom_emoji(&strip_formatting_codes(&msg.content_safe(&ctx.cache))),
+        MAX_LENGTH,
+    );
+    components.extend(text_with_links(&content));
+    for attachment in &msg.attachments {
+        components.push(TextComponent::new(" "));
+        components.push(
+            TextComponent::new(format!("[{}]", attachment.filename))
+                .with_color("aqua")
+                .with_url(&attachment.url)
+                .with_hover_text("open attachment"),
+        );
+    }
+    components
+}
+
+/// splits the text into plain text and clickable links
+fn text_with_links(text: &str) -> Vec<TextComponent> {
+    let mut components = vec![];
+    let mut plain = String::new();
+    for word in text.split_inclusive(char::is_whitespace) {
+        let trimmed = word.trim_end();
+        // discord doesn't show embeds for links in `<>`
+        let link = trimmed
+            .strip_prefix('<')
+            .and_then(|v| v.strip_suffix('>'))
+            .unwrap_or(trimmed);
+        if link.starts_with("https://") || link.starts_with("http://") {
+            if !plain.is_empty() {
+                components.push(TextComponent::new(std::mem::take(&mut plain)));
+            }
+            components.push(TextComponent::new(link).with_color("aqua").with_url(link));
+            plain.push_str(&word[trimmed.len()..]);
+        } else {
+            plain.push_str(word);
+        }
+    }
+    if !plain.is_empty() {
+        components.push(TextComponent::new(plain));
+    }
+    components
+}
+
+/// `<:name:123>` and `<a:name:123>` -> `:name:`
+fn replace_custom_emoji(text: &str) -> String {
+    let mut o = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        o.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let emoji = rest.find('>').and_then(|end| {
+            let inner = &rest[1..end];
+            let inner = inner.strip_prefix('a').unwrap_or(inner);
+            let (name, id) = inner.strip_prefix(':')?.split_once(':')?;
+            if !name.is_empty() && !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) {
+                Some((name, end))
+            } else {
+                None
+            }
+        });
+        if let Some((name, end)) = emoji {
+            o.push_str(&format!(":{name}:"));
+            rest = &rest[end + 1..];
+        } else {
+            o.push('<');
+            rest = &rest[1..];
+        }
+    }
+    o.push_str(rest);
+    o
+}
+
+/// minecraft would interpret `§` followed by a character as a color or formatting code
+fn strip_formatting_codes(text: &str) -> String {
+    text.replace('§', "")
+}
+
+fn truncate(text: &str, max_length: usize) -> String {
+    if text.chars().count() > max_length {
+        let mut o: String = text.chars().take(max_length).collect();
+        o.push('…');
+        o
+    } else {
+        text.to_owned()
+    }
+}
@@ -0,0 +1,81 @@
+//! Posting minecraft chat through a discord webhook (`mcdcbot_chat_webhook`), so that each message
+//! shows the player's name and avatar, instead of being an embed sent by the bot.
+
+use serenity::{
+    http::Http,
+    model::{id::UserId, webhook::Webhook},
+    prelude::Mutex,
+};
+
+use crate::discord_text::DiscordText;
+
+/// used if `mcdcbot_chat_avatar` isn't set
+const DEFAULT_AVATAR_TEMPLATE: &str = "https://mc-heads.net/avatar/{uuid}";
+
+pub struct ChatWebhook {
+    url: String,
+    /// to recognize (and ignore) our own messages in the chat channel
+    pub id: u64,
+    /// `{name}` and `{uuid}` are replaced with the player's name and uuid
+    avatar_template: String,
+    /// fetched from discord when the first message is sent
+    webhook: Mutex<Option<Webhook>>,
+}
+
+impl ChatWebhook {
+    /// None if `mcdcbot_chat_webhook` isn't set. panics if it isn't a valid webhook url.
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var("mcdcbot_chat_webhook").ok()?;
+        // `https://discord.com/api/webhooks/<id>/<token>`
+        let id = url
+            .split_once("/api/webhooks/")
+            .and_then(|(_, rest)| rest.split_once('/'))
+            .and_then(|(id, _token)| id.parse().ok())
+            .expect("mcdcbot_chat_webhook env var must be a discord webhook url (https://discord.com/api/webhooks/<id>/<token>)!");
+        let avatar_template = std::env::var("mcdcbot_chat_avatar")
+            .unwrap_or_else(|_| DEFAULT_AVATAR_TEMPLATE.to_owned());
+        Some(Self {
+            url,
+            id,
+            avatar_template,
+            webhook: Mutex::new(None),
+        })
+    }
+
+    /// the avatar url for a player. if the uuid isn't known, `{uuid}` is replaced with the name,
+    /// which works for avatar services that accept both.
+    pub fn avatar_url(&self, name: &str, uuid: Option<&str>) -> String {
+        self.avatar_template
+            .replace("{name}", name)
+            .replace("{uuid}", uuid.unwrap_or(name))
+    }
+
+    /// posts `message` with the player's name and avatar
+    pub async fn send(
+        &self,
+        http: &Http,
+        name: &str,
+        uuid: Option<&str>,
+        message: &DiscordText,
+    ) -> serenity::Result<()> {
+        let mut webhook = self.webhook.lock().await;
+        if webhook.is_none() {
+            *webhook = Some(Webhook::from_url(http, &self.url).await?);
+        }
+        let avatar_url = self.avatar_url(name, uuid);
+        webhook
+            .as_ref()
+            .unwrap()
+            .execute(http, false, |w| {
+                w.username(name)
+                    .avatar_url(avatar_url)
+                    .content(&message.text)
+                    .allowed_mentions(|m| {
+                        m.empty_parse()
+                            .users(message.pings.iter().map(|id| UserId(*id)))
+                    })
+            })
+            .await?;
+        Ok(())
+    }
+}
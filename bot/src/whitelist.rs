@@ -0,0 +1,165 @@
+//! Whitelist requests (mc.whitelist request). Members ask to be whitelisted, admins approve or deny
+//! the request with the buttons on it. Approved players are added with `whitelist add` if the mode
+//! is running, or written to its whitelist.json if it isn't.
+
+use std::{collections::HashMap, fmt::Display, path::Path};
+
+use serde_json::{json, Value};
+
+/// used to look up uuids for whitelist.json
+const MOJANG_PROFILE_API: &str = "https://api.mojang.com/users/profiles/minecraft";
+
+/// everything needed to handle the request is stored in the buttons' custom ids,
+/// so requests can still be approved after the bot was restarted.
+pub struct WhitelistRequest {
+    pub discord_id: u64,
+    pub name: String,
+    pub modes: Vec<String>,
+}
+
+impl WhitelistRequest {
+    /// `whitelist:<approve|deny>:<discord id>:<name>:<mode>,<mode>`, None if it would be too long for discord (100 characters)
+    pub fn custom_id(&self, approve: bool) -> Option<String> {
+        let id = format!(
+            "whitelist:{}:{}:{}:{}",
+            if approve { "approve" } else { "deny" },
+            self.discord_id,
+            self.name,
+            self.modes.join(",")
+        );
+        (id.len() <= 100).then_some(id)
+    }
+    /// returns whether the button approves the request, and the request
+    pub fn from_custom_id(custom_id: &str) -> Option<(bool, Self)> {
+        let mut parts = custom_id.strip_prefix("whitelist:")?.splitn(4, ':');
+        let approve = match parts.next()? {
+            "approve" => true,
+            "deny" => false,
+            _ => return None,
+        };
+        let discord_id = parts.next()?.parse().ok()?;
+        let name = parts.next()?.to_owned();
+        let modes = parts.next()?.split(',').map(|m| m.to_owned()).collect();
+        Some((
+            approve,
+            Self {
+                discord_id,
+                name,
+                modes,
+            },
+        ))
+    }
+}
+
+/// adds the player to whitelist.json in `directory`, for servers which aren't running.
+/// returns false if the player was already on the whitelist.
+pub async fn add_to_file(directory: &str, name: &str) -> Result<bool, WhitelistError> {
+    let directory = Path::new(directory);
+    let path = directory.join("whitelist.json");
+    let mut whitelist = match std::fs::read_to_string(&path) {
+        Ok(file) => match serde_json::from_str(&file)? {
+            Value::Array(entries) => entries,
+            _ => return Err(WhitelistError::InvalidFile),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+        Err(e) => return Err(e.into()),
+    };
+    if whitelist.iter().any(|entry| {
+        entry["name"]
+            .as_str()
+            .is_some_and(|n| n.eq_ignore_ascii_case(name))
+    }) {
+        return Ok(false);
+    }
+    let (uuid, name) = find_profile(directory, name).await?;
+    if whitelist
+        .iter()
+        .any(|entry| entry["uuid"].as_str() == Some(uuid.as_str()))
+    {
+        return Ok(false);
+    }
+    whitelist.push(json!({ "uuid": uuid, "name": name }));
+    // write to a temporary file first so a failed write can't leave a broken whitelist behind
+    let tmp_path = directory.join("whitelist.json.part");
+    std::fs::write(&tmp_path, serde_json::to_string_pretty(&whitelist)?)?;
+    std::fs::rename(&tmp_path, &path)?;
+    Ok(true)
+}
+
+/// the player's uuid and correctly capitalized name from the server's usercache.json, or from mojang's api
+async fn find_profile(directory: &Path, name: &str) -> Result<(String, String), WhitelistError> {
+    let usercache: HashMap<_, _> = crate::stats::usercache(directory)
+        .into_iter()
+        .map(|(uuid, name)| (name.to_lowercase(), (uuid, name)))
+        .collect();
+    if let Some(profile) = usercache.get(&name.to_lowercase()) {
+        return Ok(profile.clone());
+    }
+    // offline mode servers use different uuids, which we can't look up
+    let offline = std::fs::read_to_string(directory.join("server.properties"))
+        .is_ok_and(|properties| properties.lines().any(|l| l.trim() == "online-mode=false"));
+    if offline {
+        return Err(WhitelistError::OfflineMode);
+    }
+    let response = reqwest::get(format!("{MOJANG_PROFILE_API}/{name}")).await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND
+        || response.status() == reqwest::StatusCode::NO_CONTENT
+    {
+        return Err(WhitelistError::UnknownPlayer(name.to_owned()));
+    }
+    let profile: Value = serde_json::from_slice(&response.error_for_status()?.bytes().await?)?;
+    let id = profile["id"]
+        .as_str()
+        .filter(|id| id.len() == 32)
+        .ok_or_else(|| WhitelistError::UnknownPlayer(name.to_owned()))?;
+    // the api returns the uuid without dashes
+    let uuid = format!(
+        "{}-{}-{}-{}-{}",
+        &id[0..8],
+        &id[8..12],
+        &id[12..16],
+        &id[16..20],
+        &id[20..32]
+    );
+    Ok((uuid, profile["name"].as_str().unwrap_or(name).to_owned()))
+}
+
+#[derive(Debug)]
+pub enum WhitelistError {
+    UnknownPlayer(String),
+    OfflineMode,
+    InvalidFile,
+    Http(reqwest::Error),
+    Json(serde_json::Error),
+    Io(std::io::Error),
+}
+impl Display for WhitelistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownPlayer(name) => write!(f, "there is no minecraft account named {name}"),
+            Self::OfflineMode => write!(
+                f,
+                "the server is in offline mode, start it and approve the request again"
+            ),
+            Self::InvalidFile => write!(f, "whitelist.json isn't a list"),
+            Self::Http(e) => write!(f, "couldn't look up the player's uuid: {e}"),
+            Self::Json(e) => write!(f, "invalid json: {e}"),
+            Self::Io(e) => write!(f, "couldn't update whitelist.json: {e}"),
+        }
+    }
+}
+impl From<reqwest::Error> for WhitelistError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Http(e)
+    }
+}
+impl From<serde_json::Error> for WhitelistError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+impl From<std::io::Error> for WhitelistError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
@@ -2,4 +2,114 @@
 pub struct ChatMessage {
     pub author: String,
     pub message: String,
+    /// the author's uuid, if known (see `JoinLeaveEvent::uuid`)
+    pub uuid: Option<String>,
+}
+
+/// A part of a message for tellraw, see https://minecraft.wiki/w/Raw_JSON_text_format.
+/// Bedrock's tellraw only supports plain text, so it only uses `text`.
+#[derive(Clone, Debug, Default)]
+pub struct TextComponent {
+    pub text: String,
+    /// a color name like `gray`, or `#rrggbb`
+    pub color: Option<String>,
+    pub italic: bool,
+    pub underlined: bool,
+    /// opened when the text is clicked
+    pub url: Option<String>,
+    /// shown when hovering over the text
+    pub hover_text: Option<String>,
+}
+
+impl TextComponent {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+    pub fn with_color(mut self, color: impl Into<String>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+    pub fn with_italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self.underlined = true;
+        self
+    }
+    pub fn with_hover_text(mut self, text: impl Into<String>) -> Self {
+        self.hover_text = Some(text.into());
+        self
+    }
+
+    /// the component as a json object. all strings are escaped, so this is safe to use with any text.
+    pub fn to_json(&self) -> String {
+        let mut o = format!("{{\"text\":{}", json_string(&self.text));
+        if let Some(color) = &self.color {
+            o.push_str(&format!(",\"color\":{}", json_string(color)));
+        }
+        if self.italic {
+            o.push_str(",\"italic\":true");
+        }
+        if self.underlined {
+            o.push_str(",\"underlined\":true");
+        }
+        // minecraft 1.21.5 renamed clickEvent and hoverEvent (and some of their fields),
+        // older versions ignore the new names and newer versions ignore the old ones.
+        if let Some(url) = &self.url {
+            let url = json_string(url);
+            o.push_str(&format!(
+                ",\"clickEvent\":{{\"action\":\"open_url\",\"value\":{url}}},\"click_event\":{{\"action\":\"open_url\",\"url\":{url}}}"
+            ));
+        }
+        if let Some(hover_text) = &self.hover_text {
+            let text = json_string(hover_text);
+            // `value` before 1.16, `contents` since then
+            o.push_str(&format!(
+                ",\"hoverEvent\":{{\"action\":\"show_text\",\"contents\":{text},\"value\":{text}}},\"hover_event\":{{\"action\":\"show_text\",\"value\":{text}}}"
+            ));
+        }
+        o.push('}');
+        o
+    }
+}
+
+/// the components as a json array. the first element is empty,
+/// because all other elements would inherit its formatting.
+pub fn components_json(components: &[TextComponent]) -> String {
+    let mut o = "[\"\"".to_owned();
+    for component in components {
+        o.push(',');
+        o.push_str(&component.to_json());
+    }
+    o.push(']');
+    o
+}
+
+/// the text of all components, without formatting
+pub fn components_text(components: &[TextComponent]) -> String {
+    components.iter().map(|c| c.text.as_str()).collect()
+}
+
+/// encodes `text` as a json string (including the quotes), for use in commands like tellraw.
+pub fn json_string(text: &str) -> String {
+    let mut o = String::with_capacity(text.len() + 2);
+    o.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => o.push_str("\\\""),
+            '\\' => o.push_str("\\\\"),
+            '\n' => o.push_str("\\n"),
+            '\r' => o.push_str("\\r"),
+            '\t' => o.push_str("\\t"),
+            ch if ch.is_control() => o.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => o.push(ch),
+        }
+    }
+    o.push('"');
+    o
 }
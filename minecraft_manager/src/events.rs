@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 use crate::chat::ChatMessage;
 
 #[derive(Debug)]
@@ -11,6 +13,20 @@ pub enum MinecraftServerEventType {
     Warning(MinecraftServerWarning),
     JoinLeave(JoinLeaveEvent),
     ChatMessage(ChatMessage),
+    /// a player on a proxy (velocity, bungeecord) was connected to a (different) backend server.
+    BackendSwitch(BackendSwitchEvent),
+    /// the server logged a player's uuid. this is also used to set the `uuid` in later events from this player.
+    PlayerUuid(PlayerUuidEvent),
+    /// the server refuses to start because the minecraft eula has not been accepted in eula.txt
+    EulaNotAccepted,
+    /// the server logged an error which usually means it couldn't start (or is about to crash)
+    StartupFailure(StartupFailure),
+    /// `Can't keep up! Is the server overloaded? Running 2345ms or 46 ticks behind`
+    Lag(LagEvent),
+    /// output of paper's `tps` command, which the server thread runs periodically
+    Tps(TpsReport),
+    /// output of paper's `mspt` command, which the server thread runs periodically
+    Mspt(MsptReport),
 }
 
 #[derive(Debug)]
@@ -20,8 +36,91 @@ pub enum MinecraftServerWarning {
     CantWriteToStdin(std::io::Error),
 }
 
+#[derive(Debug, Clone)]
+pub enum StartupFailure {
+    /// `**** FAILED TO BIND TO PORT!`: another server (or another instance of this one) is already using the port
+    PortInUse,
+    /// `UnsupportedClassVersionError`: the server needs a newer java version.
+    /// contains the required java version, if it could be found in the error message.
+    UnsupportedJavaVersion(Option<u32>),
+    /// `OutOfMemoryError`: the server needs more ram
+    OutOfMemory,
+    /// `Unable to access jarfile`: `exec` is wrong or the jar doesn't exist
+    JarNotFound,
+    /// `session.lock: already locked`: the world is already in use by another server
+    SessionLocked,
+}
+impl Display for StartupFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PortInUse => write!(
+                f,
+                "Failed to bind to port (is another server already running on this port?)"
+            ),
+            Self::UnsupportedJavaVersion(Some(v)) => {
+                write!(
+                    f,
+                    "Wrong java version (this server needs java {v} or newer)"
+                )
+            }
+            Self::UnsupportedJavaVersion(None) => {
+                write!(
+                    f,
+                    "Wrong java version (this server needs a newer java version)"
+                )
+            }
+            Self::OutOfMemory => write!(f, "Out of memory (try increasing `ram`)"),
+            Self::JarNotFound => {
+                write!(f, "Unable to access the jar file (check `dir` and `exec`)")
+            }
+            Self::SessionLocked => write!(
+                f,
+                "The world is already in use (session.lock) by another server"
+            ),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct JoinLeaveEvent {
     pub username: String,
     pub joined: bool,
+    /// the player's uuid (like `069a79f4-44e9-4726-a5be-fca90e38aaf5`), if known.
+    /// this comes from the `UUID of player ... is ...` line logged while the player is logging in,
+    /// or from the server's usercache.json.
+    pub uuid: Option<String>,
+}
+/// `UUID of player Steve is 069a79f4-44e9-4726-a5be-fca90e38aaf5`
+#[derive(Debug)]
+pub struct PlayerUuidEvent {
+    pub username: String,
+    pub uuid: String,
+}
+#[derive(Debug)]
+pub struct BackendSwitchEvent {
+    pub username: String,
+    /// the name of the backend server, as configured in the proxy
+    pub server: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct LagEvent {
+    pub ms_behind: u64,
+    pub ticks_behind: u64,
+}
+
+/// ticks per second (20 if the server isn't lagging)
+#[derive(Debug, Clone)]
+pub struct TpsReport {
+    pub one_minute: f64,
+    pub five_minutes: f64,
+    pub fifteen_minutes: f64,
+}
+
+/// average milliseconds per tick (up to 50 if the server isn't lagging)
+#[derive(Debug, Clone)]
+pub struct MsptReport {
+    pub five_seconds: f64,
+    pub ten_seconds: f64,
+    pub one_minute: f64,
 }
@@ -0,0 +1,175 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// A java installation found by `discover`.
+#[derive(Clone, Debug)]
+pub struct JavaRuntime {
+    /// path to the `java` executable
+    pub path: PathBuf,
+    /// the full version, like `17.0.2` or `1.8.0_292`
+    pub version: String,
+    /// the major version, like 17 or 8
+    pub major: u32,
+}
+impl Display for JavaRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "java {} ({}) @ {}",
+            self.major,
+            self.version,
+            self.path.display()
+        )
+    }
+}
+
+/// finds installed java runtimes in `JAVA_HOME`, `/usr/lib/jvm`, `/usr/lib64/jvm`,
+/// sdkman's candidates directory, and `java` from PATH.
+pub fn discover() -> Vec<JavaRuntime> {
+    let mut homes = vec![];
+    if let Ok(java_home) = std::env::var("JAVA_HOME") {
+        homes.push(PathBuf::from(java_home));
+    }
+    let mut parents = vec![
+        PathBuf::from("/usr/lib/jvm"),
+        PathBuf::from("/usr/lib64/jvm"),
+    ];
+    if let Ok(sdkman) = std::env::var("SDKMAN_CANDIDATES_DIR") {
+        parents.push(Path::new(&sdkman).join("java"));
+    } else if let Ok(home) = std::env::var("HOME") {
+        parents.push(Path::new(&home).join(".sdkman/candidates/java"));
+    }
+    for parent in parents {
+        if let Ok(dir) = std::fs::read_dir(parent) {
+            let mut entries: Vec<_> = dir.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+            entries.sort();
+            homes.extend(entries);
+        }
+    }
+    let mut runtimes: Vec<JavaRuntime> = vec![];
+    let mut seen = vec![];
+    let mut add = |path: PathBuf, version: Option<String>| {
+        // the same runtime is often linked from multiple places (`current`, `default-java`, ...)
+        let canonical = std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if seen.contains(&canonical) {
+            return;
+        }
+        let version = match version {
+            Some(v) => v,
+            None => match version_from_executable(&path) {
+                Some(v) => v,
+                None => return,
+            },
+        };
+        if let Some(major) = major_version(&version) {
+            seen.push(canonical);
+            runtimes.push(JavaRuntime {
+                path,
+                version,
+                major,
+            });
+        }
+    };
+    for home in homes {
+        let java = home.join("bin/java");
+        if java.is_file() {
+            let version = version_from_release_file(&home);
+            add(java, version);
+        }
+    }
+    // `java` from PATH, which is what is used if nothing else is configured
+    if let Some(path) = std::env::var_os("PATH") {
+        if let Some(java) = std::env::split_paths(&path)
+            .map(|dir| dir.join("java"))
+            .find(|java| java.is_file())
+        {
+            add(java, None);
+        }
+    }
+    runtimes
+}
+
+/// picks the runtime with exactly the `required` major version,
+/// or the oldest runtime which is newer than that if there is none.
+pub fn select(runtimes: &[JavaRuntime], required: u32) -> Option<&JavaRuntime> {
+    runtimes.iter().find(|r| r.major == required).or_else(|| {
+        runtimes
+            .iter()
+            .filter(|r| r.major > required)
+            .min_by_key(|r| r.major)
+    })
+}
+
+/// the java version required by a minecraft version (`(minor, patch)`, for example `(20, 4)` for 1.20.4).
+pub fn required_for_minecraft(minor: u32, patch: u32) -> u32 {
+    if minor > 20 || (minor == 20 && patch >= 5) {
+        21
+    } else if minor >= 18 {
+        17
+    } else if minor == 17 {
+        16
+    } else {
+        8
+    }
+}
+
+/// finds a minecraft version (like `1.20.1` or `1.19`) in a string like `paper-1.20.1-196.jar`
+/// or `@libraries/net/minecraftforge/forge/1.20.1-47.2.0/unix_args.txt`,
+/// and returns its `(minor, patch)` version numbers.
+pub fn minecraft_version_in(s: &str) -> Option<(u32, u32)> {
+    let bytes = s.as_bytes();
+    for (i, _) in s.match_indices("1.") {
+        // don't match the `1.2` in `3.1.2` or `11.2`, but do match `minecraft_server.1.20.1.jar`
+        if i > 0
+            && (bytes[i - 1].is_ascii_digit()
+                || (bytes[i - 1] == b'.' && i > 1 && bytes[i - 2].is_ascii_digit()))
+        {
+            continue;
+        }
+        let rest = &s[i + 2..];
+        let minor_len = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        let Ok(minor) = rest[..minor_len].parse() else {
+            continue;
+        };
+        let rest = &rest[minor_len..];
+        let patch = rest
+            .strip_prefix('.')
+            .map(|rest| {
+                let patch_len = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+                rest[..patch_len].parse().unwrap_or(0)
+            })
+            .unwrap_or(0);
+        return Some((minor, patch));
+    }
+    None
+}
+
+/// reads `JAVA_VERSION="17.0.2"` from the `release` file in a java home directory
+fn version_from_release_file(home: &Path) -> Option<String> {
+    let release = std::fs::read_to_string(home.join("release")).ok()?;
+    release.lines().find_map(|line| {
+        line.strip_prefix("JAVA_VERSION=")
+            .map(|v| v.trim_matches('"').to_owned())
+    })
+}
+
+/// runs `java -version` and reads the version from `openjdk version "17.0.2" 2022-01-18`
+fn version_from_executable(java: &Path) -> Option<String> {
+    let output = Command::new(java).arg("-version").output().ok()?;
+    let output = String::from_utf8_lossy(&output.stderr);
+    let (_, rest) = output.split_once(" version \"")?;
+    let (version, _) = rest.split_once('"')?;
+    Some(version.to_owned())
+}
+
+/// `17.0.2` -> 17, `1.8.0_292` -> 8
+fn major_version(version: &str) -> Option<u32> {
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok(),
+        major => Some(major),
+    }
+}
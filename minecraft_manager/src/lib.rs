@@ -1,6 +1,8 @@
 pub mod chat;
 pub mod events;
+pub mod java;
 mod parse_line;
+pub mod resources;
 pub mod tasks;
 pub mod thread;
 mod threaded;
@@ -8,10 +10,13 @@ mod threaded;
 use std::{
     fmt::Display,
     io::BufReader,
+    path::Path,
     process::{Child, ChildStdin, ChildStdout, Command},
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
+use chat::TextComponent;
 use thread::MinecraftServerThread;
 
 #[derive(Clone)]
@@ -22,6 +27,8 @@ pub struct MinecraftServerSettings {
     /// the amount of dedicated wam for the JVM in [TODO!] (-Xm{s,x}...M)
     pub dedicated_wam: u32,
     pub java_cmd: Option<String>,
+    /// the java version to use if `java_cmd` isn't set. see `required_java_version`.
+    pub java_version: Option<u32>,
 }
 impl Display for MinecraftServerSettings {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -43,6 +50,7 @@ impl MinecraftServerSettings {
         let mut executable = Err(MinecraftServerSettingsFromLinesError::MissingExecutable);
         let mut ram = None;
         let mut java_cmd = None;
+        let mut java_version = None;
         let mut extra_line = None;
         loop {
             if let Some(line) = if let Some(l) = extra_line.take() {
@@ -56,6 +64,15 @@ impl MinecraftServerSettings {
                             server_type = Ok(match value.trim() {
                                 "vanilla-mojang" => MinecraftServerType::VanillaMojang,
                                 "vanilla-papermc" => MinecraftServerType::VanillaPaperMC,
+                                "spigot" | "bukkit" => MinecraftServerType::Spigot,
+                                "purpur" => MinecraftServerType::Purpur,
+                                "folia" => MinecraftServerType::Folia,
+                                "velocity" => MinecraftServerType::Velocity,
+                                "bungeecord" | "waterfall" => MinecraftServerType::BungeeCord,
+                                "bedrock" => MinecraftServerType::Bedrock,
+                                "fabric" => MinecraftServerType::Fabric,
+                                "forge" => MinecraftServerType::Forge,
+                                "neoforge" => MinecraftServerType::NeoForge,
                                 "custom" => {
                                     let mut name = Err(MinecraftServerSettingsFromLinesError::CustomServerTypeMissingName);
                                     let mut line_parser = Err(MinecraftServerSettingsFromLinesError::CustomServerTypeMissingLineParser);
@@ -118,6 +135,17 @@ impl MinecraftServerSettings {
                             }
                         }
                         "java_cmd" => java_cmd = Some(value.to_owned()),
+                        "java_version" => {
+                            if let Ok(v) = value.trim().parse() {
+                                java_version = Some(v);
+                            } else {
+                                return Err(
+                                    MinecraftServerSettingsFromLinesError::JavaVersionNotAnInt(
+                                        value.to_owned(),
+                                    ),
+                                );
+                            }
+                        }
                         k => {
                             return Err(MinecraftServerSettingsFromLinesError::UnknownKey(
                                 k.to_owned(),
@@ -142,6 +170,9 @@ impl MinecraftServerSettings {
         if let Some(java_cmd) = java_cmd {
             o = o.with_java_cmd(Some(java_cmd));
         }
+        if let Some(java_version) = java_version {
+            o = o.with_java_version(Some(java_version));
+        }
         Ok(o)
     }
 }
@@ -153,6 +184,7 @@ pub enum MinecraftServerSettingsFromLinesError {
     MissingDirectory,
     MissingExecutable,
     RamNotAnInt(String),
+    JavaVersionNotAnInt(String),
     CustomTypeUnknownKey(String),
     CustomServerTypeMissingName,
     CustomServerTypeMissingLineParser,
@@ -170,6 +202,7 @@ impl MinecraftServerSettings {
             executable,
             dedicated_wam: 1024,
             java_cmd: None,
+            java_version: None,
         }
     }
     pub fn with_ram(mut self, ram_mb: u32) -> Self {
@@ -180,25 +213,114 @@ impl MinecraftServerSettings {
         self.java_cmd = java_cmd;
         self
     }
+    pub fn with_java_version(mut self, java_version: Option<u32>) -> Self {
+        self.java_version = java_version;
+        self
+    }
+
+    /// sets `eula=true` in the server's eula.txt, keeping the comments minecraft puts there.
+    /// only call this if the eula has actually been accepted by someone!
+    pub fn accept_eula(&self) -> std::io::Result<()> {
+        let path = Path::new(&self.directory).join("eula.txt");
+        let mut eula = match std::fs::read_to_string(&path) {
+            Ok(v) => v
+                .lines()
+                .filter(|line| !line.trim_start().starts_with("eula="))
+                .map(|line| format!("{line}\n"))
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        eula.push_str("eula=true\n");
+        std::fs::write(path, eula)
+    }
+
+    /// looks up a player's uuid in the server's usercache.json,
+    /// which contains the players who joined in the last month.
+    pub fn uuid_from_usercache(&self, username: &str) -> Option<String> {
+        let usercache =
+            std::fs::read_to_string(Path::new(&self.directory).join("usercache.json")).ok()?;
+        // `[{"name":"Steve","uuid":"069a79f4-...","expiresOn":"..."},...]`
+        let string_field = |entry: &str, key: &str| -> Option<String> {
+            let (_, rest) = entry.split_once(&format!("\"{key}\""))?;
+            let rest = rest
+                .trim_start()
+                .strip_prefix(':')?
+                .trim_start()
+                .strip_prefix('"')?;
+            let (value, _) = rest.split_once('"')?;
+            Some(value.to_owned())
+        };
+        usercache.split('}').find_map(|entry| {
+            if string_field(entry, "name")?.eq_ignore_ascii_case(username) {
+                string_field(entry, "uuid")
+            } else {
+                None
+            }
+        })
+    }
+
+    /// the java version this server needs: `java_version` if it was set,
+    /// otherwise guessed from the minecraft version in `exec` (like `paper-1.20.1-196.jar`).
+    pub fn required_java_version(&self) -> Option<u32> {
+        if self.java_version.is_some() {
+            return self.java_version;
+        }
+        if self.server_type.is_proxy() {
+            // proxy versions aren't minecraft versions
+            return None;
+        }
+        let (minor, patch) = java::minecraft_version_in(&self.executable)?;
+        Some(java::required_for_minecraft(minor, patch))
+    }
+    /// picks one of the installed java runtimes for this server, if `required_java_version` is known.
+    /// this isn't used if `java_cmd` is set.
+    pub fn select_java(&self) -> Option<java::JavaRuntime> {
+        let required = self.required_java_version()?;
+        java::select(&java::discover(), required).cloned()
+    }
 
     pub fn get_command(&self) -> Command {
-        let mut cmd = Command::new(if let Some(c) = &self.java_cmd {
-            c.as_str()
+        if let MinecraftServerType::Bedrock = &self.server_type {
+            // bedrock_server is a native executable, so there is no jvm to configure.
+            // the program's path has to be absolute, because relative paths may or may not be
+            // resolved from current_dir, so a relative `dir` is made absolute first.
+            let directory = std::fs::canonicalize(&self.directory)
+                .unwrap_or_else(|_| Path::new(&self.directory).to_path_buf());
+            let mut cmd = Command::new(directory.join(&self.executable));
+            cmd.current_dir(&directory);
+            // bedrock_server needs to find the libraries next to it
+            cmd.env("LD_LIBRARY_PATH", &directory);
+            return cmd;
+        }
+        // None = pick a java runtime
+        let program = if let Some(c) = &self.java_cmd {
+            Some(c.as_str())
         } else {
             match &self.server_type {
-                MinecraftServerType::VanillaMojang => "java", // "/usr/lib/jvm/openjdk17/bin/java",
-                MinecraftServerType::VanillaPaperMC => "java", // "/usr/lib/jvm/openjdk17/bin/java",
+                MinecraftServerType::VanillaMojang => None,
+                MinecraftServerType::VanillaPaperMC => None,
+                MinecraftServerType::Spigot
+                | MinecraftServerType::Purpur
+                | MinecraftServerType::Folia
+                | MinecraftServerType::Velocity
+                | MinecraftServerType::BungeeCord
+                | MinecraftServerType::Fabric
+                | MinecraftServerType::Forge
+                | MinecraftServerType::NeoForge => None,
+                MinecraftServerType::Bedrock => unreachable!("bedrock servers don't use java"),
                 MinecraftServerType::Custom {
                     command_override, ..
-                } => {
-                    if let Some(cmd) = command_override {
-                        cmd
-                    } else {
-                        "java"
-                    }
-                }
+                } => command_override.as_deref(),
             }
-        });
+        };
+        let mut cmd = match program {
+            Some(program) => Command::new(program),
+            None => match self.select_java() {
+                Some(java) => Command::new(java.path),
+                None => Command::new("java"),
+            },
+        };
         cmd.current_dir(&self.directory);
         // match &self.server_type {
         //     MinecraftServerType::VanillaMojang | MinecraftServerType::VanillaPaperMC =>
@@ -208,10 +330,17 @@ impl MinecraftServerSettings {
             "-Dsun.stdout.encoding=UTF-8".to_owned(),
             "-Dsun.stderr.encoding=UTF-8".to_owned(),
             "-DFile.Encoding=UTF-8".to_owned(),
-            "-jar".to_string(),
-            self.executable.to_string(),
-            "nogui".to_string(),
         ]);
+        if self.executable.starts_with('@') {
+            // an argument file, like forge's and neoforge's `@libraries/.../unix_args.txt`,
+            // which contains the classpath and main class instead of a jar.
+            cmd.arg(&self.executable);
+        } else {
+            cmd.args(["-jar", self.executable.as_str()]);
+        }
+        if !self.server_type.is_proxy() {
+            cmd.arg("nogui");
+        }
         cmd
     }
 }
@@ -220,6 +349,27 @@ impl MinecraftServerSettings {
 pub enum MinecraftServerType {
     VanillaMojang,
     VanillaPaperMC,
+    /// spigot or craftbukkit.
+    Spigot,
+    /// purpur, a paper fork with some additional chat formats.
+    Purpur,
+    /// folia, a paper fork which ticks regions of the world on different threads.
+    Folia,
+    /// the velocity proxy. players are reported as online while they are connected to the proxy,
+    /// and switching between backend servers produces `BackendSwitch` events.
+    Velocity,
+    /// the bungeecord (or waterfall) proxy. see `Velocity`.
+    BungeeCord,
+    /// the bedrock dedicated server. `exec` should be `bedrock_server`, and `ram` and `java_cmd` are ignored.
+    /// bedrock servers don't log chat messages, so only join/leave events are available.
+    Bedrock,
+    /// fabric, started using the fabric server launcher jar.
+    Fabric,
+    /// forge. since 1.17, forge servers don't have a server jar anymore,
+    /// so `exec` should be `@libraries/net/minecraftforge/forge/<version>/unix_args.txt`.
+    Forge,
+    /// neoforge. like forge, `exec` should be `@libraries/net/neoforged/neoforge/<version>/unix_args.txt`.
+    NeoForge,
     Custom {
         /// your custom server type's name
         name: String,
@@ -238,12 +388,75 @@ impl Display for MinecraftServerType {
         match self {
             Self::VanillaMojang => write!(f, "vanilla-mojang"),
             Self::VanillaPaperMC => write!(f, "vanilla-papermc"),
+            Self::Spigot => write!(f, "spigot"),
+            Self::Purpur => write!(f, "purpur"),
+            Self::Folia => write!(f, "folia"),
+            Self::Velocity => write!(f, "velocity"),
+            Self::BungeeCord => write!(f, "bungeecord"),
+            Self::Bedrock => write!(f, "bedrock"),
+            Self::Fabric => write!(f, "fabric"),
+            Self::Forge => write!(f, "forge"),
+            Self::NeoForge => write!(f, "neoforge"),
             Self::Custom {
                 name: identifier, ..
             } => write!(f, "custom ({identifier})"),
         }
     }
 }
+impl MinecraftServerType {
+    /// true for proxies like velocity and bungeecord, which don't run a world themselves.
+    pub fn is_proxy(&self) -> bool {
+        matches!(self, Self::Velocity | Self::BungeeCord)
+    }
+    /// the command which is written to stdin to stop the server.
+    pub fn stop_command(&self) -> &'static str {
+        if self.is_proxy() {
+            "end"
+        } else {
+            "stop"
+        }
+    }
+    /// true for servers with paper's `tps` and `mspt` commands, which are run periodically
+    /// to produce `Tps` and `Mspt` events.
+    pub fn supports_tps_command(&self) -> bool {
+        matches!(self, Self::VanillaPaperMC | Self::Purpur)
+    }
+    /// how long to wait for the server to exit after the stop command was sent before killing it.
+    /// bedrock_server sometimes prints `Quit correctly` but keeps running, so it gets a timeout.
+    pub fn stop_timeout(&self) -> Option<Duration> {
+        match self {
+            Self::Bedrock => Some(Duration::from_secs(30)),
+            _ => None,
+        }
+    }
+    /// the command which shows the message to all players in chat, without any prefix.
+    /// bedrock only gets the text, without formatting.
+    /// `None` for proxies, which don't have a tellraw command.
+    pub fn tellraw_command(&self, message: &[TextComponent]) -> Option<String> {
+        self.tellraw_command_for("@a", message)
+    }
+    /// like `tellraw_command`, but only shows the message to `target`, which is a player name or a selector.
+    /// `target` isn't escaped, so check player names with `is_valid_player_name` first!
+    pub fn tellraw_command_for(&self, target: &str, message: &[TextComponent]) -> Option<String> {
+        match self {
+            Self::Velocity | Self::BungeeCord => None,
+            Self::Bedrock => Some(format!(
+                "tellraw {target} {{\"rawtext\":[{{\"text\":{}}}]}}",
+                chat::json_string(&chat::components_text(message))
+            )),
+            _ => Some(format!(
+                "tellraw {target} {}",
+                chat::components_json(message)
+            )),
+        }
+    }
+}
+
+/// java edition player names: 3-16 characters, only letters, numbers and `_`.
+/// (some servers allow shorter names, and bedrock names are different, but these are what we can safely put in commands)
+pub fn is_valid_player_name(name: &str) -> bool {
+    (1..=16).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
 
 pub fn test() {
     // create minecraft server config
@@ -253,6 +466,7 @@ pub fn test() {
         executable: "paper-1.19-81.jar".to_string(),
         dedicated_wam: 1024,
         java_cmd: None,
+        java_version: None,
     };
     // start server
     let mut thread = minecraft_server_settings.spawn();
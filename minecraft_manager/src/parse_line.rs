@@ -5,7 +5,7 @@ use std::{
 
 use crate::{
     chat::ChatMessage,
-    events::{self, MinecraftServerEventType},
+    events::{self, LagEvent, MinecraftServerEventType, MsptReport, StartupFailure, TpsReport},
     MinecraftServerSettings, MinecraftServerType,
 };
 
@@ -24,6 +24,26 @@ pub fn parse_line(line: &str, settings: &MinecraftServerSettings) -> ParseOutput
     if line.trim().is_empty() {
         return ParseOutput::Nothing;
     }
+    if let Some(failure) = parse_startup_failure(line) {
+        return ParseOutput::Event(MinecraftServerEventType::StartupFailure(failure));
+    }
+    if let Some(lag) = parse_lag(line) {
+        return ParseOutput::Event(MinecraftServerEventType::Lag(lag));
+    }
+    if settings.server_type.supports_tps_command() {
+        if let Some(output) = parse_tps_command_output(line) {
+            return output;
+        }
+    }
+    // this is logged differently by different server types, but the message is always the same
+    if log_message(line).trim()
+        == "You need to agree to the EULA in order to run the server. Go to eula.txt for more info."
+    {
+        return ParseOutput::Event(MinecraftServerEventType::EulaNotAccepted);
+    }
+    if let Some(event) = parse_player_uuid(line) {
+        return ParseOutput::Event(MinecraftServerEventType::PlayerUuid(event));
+    }
     match &settings.server_type {
         MinecraftServerType::Custom {
             line_parser,
@@ -78,6 +98,7 @@ pub fn parse_line(line: &str, settings: &MinecraftServerSettings) -> ParseOutput
                         b'c' => {
                             ParseOutput::Event(MinecraftServerEventType::ChatMessage(ChatMessage {
                                 author: buf[1..].to_owned(),
+                                uuid: None,
                                 message: {
                                     let mut o = String::new();
                                     if let Err(e) = stdout.read_line(&mut o) {
@@ -94,12 +115,14 @@ pub fn parse_line(line: &str, settings: &MinecraftServerSettings) -> ParseOutput
                             events::JoinLeaveEvent {
                                 username: buf[1..].to_owned(),
                                 joined: true,
+                                uuid: None,
                             },
                         )),
                         b'l' => ParseOutput::Event(MinecraftServerEventType::JoinLeave(
                             events::JoinLeaveEvent {
                                 username: buf[1..].to_owned(),
                                 joined: false,
+                                uuid: None,
                             },
                         )),
                         b'e' => ParseOutput::Error({
@@ -130,6 +153,7 @@ pub fn parse_line(line: &str, settings: &MinecraftServerSettings) -> ParseOutput
                         return ParseOutput::Event(MinecraftServerEventType::ChatMessage(
                             ChatMessage {
                                 author: user.to_owned(),
+                                uuid: None,
                                 message: msg.to_owned(),
                             },
                         ));
@@ -139,6 +163,7 @@ pub fn parse_line(line: &str, settings: &MinecraftServerSettings) -> ParseOutput
                         events::JoinLeaveEvent {
                             username: rest[0..rest.len() - " joined the game".len()].to_owned(),
                             joined: true,
+                            uuid: None,
                         },
                     ));
                 } else if rest.ends_with(" left the game") {
@@ -146,6 +171,7 @@ pub fn parse_line(line: &str, settings: &MinecraftServerSettings) -> ParseOutput
                         events::JoinLeaveEvent {
                             username: rest[0..rest.len() - " left the game".len()].to_owned(),
                             joined: false,
+                            uuid: None,
                         },
                     ));
                 }
@@ -171,6 +197,7 @@ pub fn parse_line(line: &str, settings: &MinecraftServerSettings) -> ParseOutput
                                                     MinecraftServerEventType::ChatMessage(
                                                         ChatMessage {
                                                             author: username.to_string(),
+                                                            uuid: None,
                                                             message: message[1..].to_string(),
                                                         },
                                                     ),
@@ -184,6 +211,7 @@ pub fn parse_line(line: &str, settings: &MinecraftServerSettings) -> ParseOutput
                                                     events::JoinLeaveEvent {
                                                         username: username.to_string(),
                                                         joined: true,
+                                                        uuid: None,
                                                     },
                                                 ),
                                             );
@@ -195,6 +223,7 @@ pub fn parse_line(line: &str, settings: &MinecraftServerSettings) -> ParseOutput
                                                     events::JoinLeaveEvent {
                                                         username: username.to_string(),
                                                         joined: false,
+                                                        uuid: None,
                                                     },
                                                 ),
                                             );
@@ -210,5 +239,855 @@ pub fn parse_line(line: &str, settings: &MinecraftServerSettings) -> ParseOutput
             }
             ParseOutput::Nothing
         }
+        MinecraftServerType::Spigot => {
+            if let Some(message) = strip_bukkit_info_prefix(line) {
+                return parse_vanilla_message(message);
+            }
+            ParseOutput::Nothing
+        }
+        MinecraftServerType::Purpur => {
+            if let Some(message) = strip_bukkit_info_prefix(line) {
+                // emotes (/me) are logged as `* Steve waves`
+                if let Some((author, _action)) =
+                    message.strip_prefix("* ").and_then(|v| v.split_once(' '))
+                {
+                    return ParseOutput::Event(MinecraftServerEventType::ChatMessage(
+                        ChatMessage {
+                            author: author.to_owned(),
+                            uuid: None,
+                            message: message.trim().to_owned(),
+                        },
+                    ));
+                }
+                return parse_vanilla_message(message);
+            }
+            ParseOutput::Nothing
+        }
+        MinecraftServerType::Folia => {
+            if let Some(message) = strip_bukkit_info_prefix(line) {
+                return parse_vanilla_message(message);
+            }
+            // folia's log4j format includes the thread, and events from players
+            // are logged from whichever region thread is ticking that player.
+            if let Some(log_line) = LogLine::parse(line) {
+                if log_line.severity == "INFO"
+                    && log_line.logger.is_none()
+                    && (log_line.thread == "Server thread"
+                        || log_line.thread.starts_with("Region Scheduler Thread")
+                        || log_line.thread.starts_with("Async Chat Thread"))
+                {
+                    return parse_vanilla_message(log_line.message);
+                }
+            }
+            ParseOutput::Nothing
+        }
+        MinecraftServerType::Velocity => {
+            if let Some(message) = strip_bukkit_info_prefix(line) {
+                if let Some(rest) = message.strip_prefix("[connected player] ") {
+                    // `Steve (/127.0.0.1:51234) has connected`, the disconnect message can have a reason
+                    if let Some((username, rest)) = rest
+                        .split_once(" (/")
+                        .and_then(|(username, rest)| Some((username, rest.split_once(") ")?.1)))
+                    {
+                        if rest == "has connected" {
+                            return join_leave(username, true);
+                        } else if rest == "has disconnected"
+                            || rest.starts_with("has disconnected: ")
+                        {
+                            return join_leave(username, false);
+                        }
+                    }
+                } else if let Some(rest) = message.strip_prefix("[server connection] ") {
+                    // `Steve -> lobby has connected`
+                    if let Some((username, server)) = rest
+                        .strip_suffix(" has connected")
+                        .and_then(|v| v.split_once(" -> "))
+                    {
+                        return backend_switch(username, server);
+                    }
+                }
+            }
+            ParseOutput::Nothing
+        }
+        MinecraftServerType::BungeeCord => {
+            // waterfall uses `[12:34:56 INFO]: `, bungeecord uses `12:34:56 [INFO] `
+            let message = if let Some(message) = strip_bukkit_info_prefix(line) {
+                message
+            } else if let Some((_time, message)) = line
+                .split_once(" [INFO] ")
+                .filter(|(time, _)| is_log_time(time))
+            {
+                message
+            } else {
+                return ParseOutput::Nothing;
+            };
+            // `[Steve] <-> ServerConnector [lobby] has connected`,
+            // `[/127.0.0.1:51234|Steve] <-> InitialHandler has connected`
+            if let Some((connection, rest)) =
+                message.strip_prefix('[').and_then(|v| v.split_once("] "))
+            {
+                let username = if let Some((a, b)) = connection.split_once('|') {
+                    if a.starts_with('/') {
+                        b
+                    } else {
+                        a
+                    }
+                } else {
+                    connection
+                };
+                if username.starts_with('/') {
+                    // not logged in yet
+                } else if rest == "<-> InitialHandler has connected" {
+                    return join_leave(username, true);
+                } else if rest == "-> UpstreamBridge has disconnected" {
+                    return join_leave(username, false);
+                } else if let Some(server) = rest
+                    .strip_prefix("<-> ServerConnector [")
+                    .and_then(|v| v.strip_suffix("] has connected"))
+                {
+                    return backend_switch(username, server);
+                }
+            }
+            ParseOutput::Nothing
+        }
+        MinecraftServerType::Bedrock => {
+            // `[2024-01-01 12:00:00:123 INFO] Player connected: Steve, xuid: 2535...`
+            if let Some((_time, message)) = line
+                .strip_prefix('[')
+                .and_then(|v| v.split_once(" INFO] "))
+                .filter(|(time, _)| is_log_time(time))
+            {
+                if let Some((username, _)) = message
+                    .strip_prefix("Player connected: ")
+                    .and_then(|v| v.split_once(", xuid: "))
+                {
+                    return join_leave(username, true);
+                } else if let Some((username, _)) = message
+                    .strip_prefix("Player disconnected: ")
+                    .and_then(|v| v.split_once(", xuid: "))
+                {
+                    return join_leave(username, false);
+                }
+            }
+            ParseOutput::Nothing
+        }
+        MinecraftServerType::Fabric
+        | MinecraftServerType::Forge
+        | MinecraftServerType::NeoForge => {
+            if let Some(log_line) = LogLine::parse(line) {
+                // mods can log whatever they want, so only look at messages from minecraft itself
+                if log_line.thread == "Server thread"
+                    && log_line.severity == "INFO"
+                    && log_line.logger.is_none_or(|logger| {
+                        logger == "Minecraft"
+                            || logger.trim_end_matches('/').ends_with("MinecraftServer")
+                    })
+                {
+                    return parse_vanilla_message(log_line.message);
+                }
+            }
+            ParseOutput::Nothing
+        }
+    }
+}
+
+/// checks for errors which usually mean that the server didn't start (or is about to crash).
+/// these are printed to stdout or stderr, and are the same for all java-based server types.
+pub fn parse_startup_failure(line: &str) -> Option<StartupFailure> {
+    // only the log message itself, so players can't fake these in chat
+    let message = log_message(line).trim();
+    if message.starts_with("**** FAILED TO BIND TO PORT!") {
+        return Some(StartupFailure::PortInUse);
+    } else if message.starts_with("Error: Unable to access jarfile") {
+        return Some(StartupFailure::JarNotFound);
+    }
+    // exceptions are printed as `java.lang.OutOfMemoryError: Java heap space`,
+    // `Exception in thread "main" java.lang.OutOfMemoryError: ...` or `Caused by: ...`
+    let message = message.strip_prefix("Caused by: ").unwrap_or(message);
+    let exception = match message.strip_prefix("Exception in thread \"") {
+        Some(rest) => rest.split_once("\" ")?.1,
+        None => message,
+    };
+    let (class, exception_message) = exception.split_once(": ").unwrap_or((exception, ""));
+    if class.contains(' ') {
+        None
+    } else if class == "java.lang.UnsupportedClassVersionError" {
+        // `... (class file version 61.0), this version of the Java Runtime only recognizes class file versions up to 52.0`
+        // class file version 61 is java 17, 65 is java 21, ...
+        let required_java = exception_message
+            .split_once("(class file version ")
+            .and_then(|(_, v)| v.split_once('.'))
+            .and_then(|(v, _)| v.parse::<u32>().ok())
+            .and_then(|v| v.checked_sub(44));
+        Some(StartupFailure::UnsupportedJavaVersion(required_java))
+    } else if class == "java.lang.OutOfMemoryError" {
+        Some(StartupFailure::OutOfMemory)
+    } else if exception_message.contains("session.lock")
+        && exception_message.contains("already locked")
+    {
+        Some(StartupFailure::SessionLocked)
+    } else {
+        None
+    }
+}
+
+/// `Done (3.456s)! For help, type "help"`, which the server logs once it has started
+pub fn is_done_line(line: &str) -> bool {
+    log_message(line).starts_with("Done (")
+}
+
+/// `Can't keep up! Is the server overloaded? Running 2345ms or 46 ticks behind`
+fn parse_lag(line: &str) -> Option<LagEvent> {
+    // only the log message itself, so players can't fake this in chat
+    let rest =
+        log_message(line).strip_prefix("Can't keep up! Is the server overloaded? Running ")?;
+    let (ms_behind, rest) = rest.split_once("ms or ")?;
+    let (ticks_behind, _) = rest.split_once(" ticks behind")?;
+    Some(LagEvent {
+        ms_behind: ms_behind.parse().ok()?,
+        ticks_behind: ticks_behind.parse().ok()?,
+    })
+}
+
+/// the output of paper's `tps` and `mspt` commands:
+/// - `TPS from last 1m, 5m, 15m: 20.0, *20.0, 19.8` (`*` means the server is catching up)
+/// - `Server tick times (avg/min/max) from last 5s, 10s, 1m:` followed by
+///   `◴ 2.1/1.5/5.3, 2.0/1.4/6.1, 2.1/1.3/10.2`
+fn parse_tps_command_output(line: &str) -> Option<ParseOutput> {
+    let line = strip_color_codes(line);
+    // like parse_lag, the output has to be the whole log message
+    let message = log_message(&line).trim_start();
+    if let Some(tps) = message.strip_prefix("TPS from last 1m, 5m, 15m: ") {
+        let tps: Vec<f64> = tps
+            .split(',')
+            .filter_map(|v| v.trim().trim_start_matches('*').parse().ok())
+            .collect();
+        if let [one_minute, five_minutes, fifteen_minutes] = tps[..] {
+            return Some(ParseOutput::Event(MinecraftServerEventType::Tps(
+                TpsReport {
+                    one_minute,
+                    five_minutes,
+                    fifteen_minutes,
+                },
+            )));
+        }
+    } else if let Some(mspt) = message.strip_prefix("◴ ") {
+        // only the averages
+        let mspt: Vec<f64> = mspt
+            .split(',')
+            .filter_map(|v| v.trim().split('/').next()?.parse().ok())
+            .collect();
+        if let [five_seconds, ten_seconds, one_minute] = mspt[..] {
+            return Some(ParseOutput::Event(MinecraftServerEventType::Mspt(
+                MsptReport {
+                    five_seconds,
+                    ten_seconds,
+                    one_minute,
+                },
+            )));
+        }
+    }
+    None
+}
+
+/// `UUID of player Steve is 069a79f4-44e9-4726-a5be-fca90e38aaf5`, which vanilla-based servers
+/// log from the `User Authenticator` thread while a player is logging in.
+fn parse_player_uuid(line: &str) -> Option<events::PlayerUuidEvent> {
+    // this has to be the whole log message, so players can't fake it in chat.
+    // the bukkit prefix ends before anything a player wrote, so it has to be checked first.
+    let message = match strip_bukkit_info_prefix(line) {
+        Some(message) => message,
+        None => match LogLine::parse(line) {
+            Some(log_line) if log_line.thread.starts_with("User Authenticator") => log_line.message,
+            _ => return None,
+        },
+    };
+    let (username, uuid) = message
+        .strip_prefix("UUID of player ")?
+        .split_once(" is ")?;
+    let uuid = uuid.trim();
+    if username.contains(' ')
+        || uuid.len() != 36
+        || !uuid.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+    {
+        return None;
+    }
+    Some(events::PlayerUuidEvent {
+        username: username.to_owned(),
+        uuid: uuid.to_owned(),
+    })
+}
+
+/// removes minecraft's `§x` formatting codes
+fn strip_color_codes(text: &str) -> String {
+    let mut o = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '§' {
+            chars.next();
+        } else {
+            o.push(ch);
+        }
+    }
+    o
+}
+
+/// A log4j-style log line, as printed by fabric, forge and neoforge:
+/// - `[12:34:56] [Server thread/INFO]: message`
+/// - `[12:34:56] [Server thread/INFO] (Minecraft) message` (fabric)
+/// - `[12:34:56] [Server thread/INFO] [minecraft/MinecraftServer]: message` (forge)
+/// - `[12Jan2024 12:34:56.789] [Server thread/INFO] [net.minecraft.server.MinecraftServer/]: message` (forge, neoforge)
+struct LogLine<'a> {
+    thread: &'a str,
+    severity: &'a str,
+    logger: Option<&'a str>,
+    message: &'a str,
+}
+impl<'a> LogLine<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let (time, rest) = line.strip_prefix('[')?.split_once("] [")?;
+        // otherwise a chat message containing `] [` could look like the rest of the prefix
+        if !is_log_time(time) {
+            return None;
+        }
+        let (thread_and_severity, rest) = rest.split_once(']')?;
+        let (thread, severity) = thread_and_severity.rsplit_once('/')?;
+        let (logger, message) = if let Some(message) = rest.strip_prefix(": ") {
+            (None, message)
+        } else if let Some(rest) = rest.strip_prefix(" [") {
+            let (logger, message) = rest.split_once("]: ")?;
+            (Some(logger), message)
+        } else if let Some(rest) = rest.strip_prefix(" (") {
+            let (logger, message) = rest.split_once(") ")?;
+            (Some(logger), message)
+        } else {
+            return None;
+        };
+        Some(Self {
+            thread,
+            severity,
+            logger,
+            message,
+        })
+    }
+}
+
+/// the message of a log line, without the time, thread and severity (see `LogLine` and
+/// `strip_bukkit_info_prefix`). lines which don't look like log lines, like exceptions
+/// printed to stderr, are returned as they are.
+fn log_message(line: &str) -> &str {
+    // the bukkit prefix ends before anything a player wrote, so it has to be checked first
+    if let Some((_severity, message)) = bukkit_prefix(line) {
+        return message;
+    }
+    if let Some(log_line) = LogLine::parse(line) {
+        return log_line.message;
+    }
+    line
+}
+
+/// `12:34:56`, `12Jan2024 12:34:56.789`, `2024-01-01 12:00:00:123`
+fn is_log_time(time: &str) -> bool {
+    time.starts_with(|c: char| c.is_ascii_digit())
+        && time
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '.' | ',' | '-' | ' '))
+}
+
+/// splits the `[12:34:56 INFO]: ` prefix used by bukkit-based servers (spigot, paper, purpur, folia)
+/// off the line. returns the severity and the message.
+fn bukkit_prefix(line: &str) -> Option<(&str, &str)> {
+    let (time, rest) = line.strip_prefix('[')?.split_once(' ')?;
+    let (severity, message) = rest.split_once("]: ")?;
+    if !is_log_time(time)
+        || severity.is_empty()
+        || !severity.chars().all(|c| c.is_ascii_uppercase())
+    {
+        return None;
+    }
+    Some((severity, message))
+}
+
+/// strips the `[12:34:56 INFO]: ` prefix used by bukkit-based servers (spigot, paper, purpur, folia).
+/// returns `None` if the line doesn't have this prefix or wasn't logged with INFO severity.
+fn strip_bukkit_info_prefix(line: &str) -> Option<&str> {
+    match bukkit_prefix(line)? {
+        ("INFO", message) => Some(message),
+        _ => None,
+    }
+}
+
+/// parses chat messages and join/leave messages, which look the same on most server types
+/// once the time, thread and severity have been removed.
+fn parse_vanilla_message(message: &str) -> ParseOutput {
+    let message = message.trim();
+    // since 1.19.1, messages without a valid chat signature are prefixed with this
+    let message = message.strip_prefix("[Not Secure] ").unwrap_or(message);
+    if let Some(rest) = message.strip_prefix('<') {
+        if let Some((author, message)) = rest.split_once("> ") {
+            return ParseOutput::Event(MinecraftServerEventType::ChatMessage(ChatMessage {
+                author: author.to_owned(),
+                uuid: None,
+                message: message.to_owned(),
+            }));
+        }
+    } else if let Some(username) = message.strip_suffix(" joined the game") {
+        return ParseOutput::Event(MinecraftServerEventType::JoinLeave(
+            events::JoinLeaveEvent {
+                username: username.to_owned(),
+                joined: true,
+                uuid: None,
+            },
+        ));
+    } else if let Some(username) = message.strip_suffix(" left the game") {
+        return ParseOutput::Event(MinecraftServerEventType::JoinLeave(
+            events::JoinLeaveEvent {
+                username: username.to_owned(),
+                joined: false,
+                uuid: None,
+            },
+        ));
+    }
+    ParseOutput::Nothing
+}
+
+fn join_leave(username: &str, joined: bool) -> ParseOutput {
+    ParseOutput::Event(MinecraftServerEventType::JoinLeave(
+        events::JoinLeaveEvent {
+            username: username.to_owned(),
+            joined,
+            uuid: None,
+        },
+    ))
+}
+
+fn backend_switch(username: &str, server: &str) -> ParseOutput {
+    ParseOutput::Event(MinecraftServerEventType::BackendSwitch(
+        events::BackendSwitchEvent {
+            username: username.to_owned(),
+            server: server.to_owned(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(server_type: &str) -> MinecraftServerSettings {
+        let lines = format!("type={server_type}\ndir=.\nexec=server.jar");
+        MinecraftServerSettings::from_lines(&mut lines.lines())
+            .ok()
+            .unwrap()
+    }
+
+    /// the event from the line, as `join Steve`, `leave Steve`, `chat Steve: hi`,
+    /// `switch Steve lobby` or `nothing`
+    fn event(server_type: &str, line: &str) -> String {
+        match parse_line(line, &settings(server_type)) {
+            ParseOutput::Event(MinecraftServerEventType::JoinLeave(ev)) => format!(
+                "{} {}",
+                if ev.joined { "join" } else { "leave" },
+                ev.username
+            ),
+            ParseOutput::Event(MinecraftServerEventType::ChatMessage(ev)) => {
+                format!("chat {}: {}", ev.author, ev.message)
+            }
+            ParseOutput::Event(MinecraftServerEventType::BackendSwitch(ev)) => {
+                format!("switch {} {}", ev.username, ev.server)
+            }
+            ParseOutput::Event(other) => format!("{other:?}"),
+            ParseOutput::Error(_) => "error".to_owned(),
+            ParseOutput::Nothing => "nothing".to_owned(),
+        }
+    }
+
+    fn is_chat(output: ParseOutput) -> bool {
+        matches!(
+            output,
+            ParseOutput::Event(MinecraftServerEventType::ChatMessage(_))
+        )
+    }
+
+    #[test]
+    fn modded() {
+        for (server_type, line, expected) in [
+            // fabric
+            (
+                "fabric",
+                "[12:34:56] [Server thread/INFO] (Minecraft) Steve joined the game",
+                "join Steve",
+            ),
+            (
+                "fabric",
+                "[12:34:56] [Server thread/INFO]: Steve left the game",
+                "leave Steve",
+            ),
+            (
+                "fabric",
+                "[12:34:56] [Server thread/INFO] (Minecraft) <Steve> hi",
+                "chat Steve: hi",
+            ),
+            (
+                "fabric",
+                "[12:34:56] [Server thread/INFO] (Minecraft) <Eve> Steve joined the game",
+                "chat Eve: Steve joined the game",
+            ),
+            // mods can log anything
+            (
+                "fabric",
+                "[12:34:56] [Server thread/INFO] (somemod) Steve joined the game",
+                "nothing",
+            ),
+            // forge
+            (
+                "forge",
+                "[12Jan2024 12:34:56.789] [Server thread/INFO] [net.minecraft.server.MinecraftServer/]: Steve joined the game",
+                "join Steve",
+            ),
+            (
+                "forge",
+                "[12:34:56] [Server thread/INFO] [minecraft/MinecraftServer]: Steve left the game",
+                "leave Steve",
+            ),
+            (
+                "forge",
+                "[12Jan2024 12:34:56.789] [Server thread/INFO] [net.minecraft.server.MinecraftServer/]: <Eve> Steve left the game",
+                "chat Eve: Steve left the game",
+            ),
+            (
+                "forge",
+                "[12Jan2024 12:34:56.789] [Server thread/INFO] [somemod/]: Steve joined the game",
+                "nothing",
+            ),
+            // neoforge
+            (
+                "neoforge",
+                "[12Jan2024 12:34:56.789] [Server thread/INFO] [net.minecraft.server.MinecraftServer/]: [Not Secure] <Steve> hi",
+                "chat Steve: hi",
+            ),
+            (
+                "neoforge",
+                "[12Jan2024 12:34:56.789] [Server thread/INFO] [net.minecraft.server.MinecraftServer/]: <Eve> a] [Server thread/INFO]: Steve joined the game",
+                "chat Eve: a] [Server thread/INFO]: Steve joined the game",
+            ),
+        ] {
+            assert_eq!(event(server_type, line), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn bukkit() {
+        for (server_type, line, expected) in [
+            (
+                "spigot",
+                "[12:34:56 INFO]: Steve joined the game",
+                "join Steve",
+            ),
+            (
+                "spigot",
+                "[12:34:56 INFO]: Steve left the game",
+                "leave Steve",
+            ),
+            (
+                "spigot",
+                "[12:34:56 INFO]: [Not Secure] <Steve> hi",
+                "chat Steve: hi",
+            ),
+            (
+                "spigot",
+                "[12:34:56 INFO]: <Eve> Steve left the game",
+                "chat Eve: Steve left the game",
+            ),
+            (
+                "spigot",
+                "[12:34:56 WARN]: Steve joined the game",
+                "nothing",
+            ),
+            // purpur
+            (
+                "purpur",
+                "[12:34:56 INFO]: Steve joined the game",
+                "join Steve",
+            ),
+            (
+                "purpur",
+                "[12:34:56 INFO]: * Steve waves",
+                "chat Steve: * Steve waves",
+            ),
+            (
+                "purpur",
+                "[12:34:56 INFO]: <Eve> Steve joined the game",
+                "chat Eve: Steve joined the game",
+            ),
+            // folia
+            (
+                "folia",
+                "[12:34:56 INFO]: Steve left the game",
+                "leave Steve",
+            ),
+            (
+                "folia",
+                "[12:34:56] [Region Scheduler Thread #2/INFO]: Steve joined the game",
+                "join Steve",
+            ),
+            (
+                "folia",
+                "[12:34:56] [Async Chat Thread - #0/INFO]: <Steve> hi",
+                "chat Steve: hi",
+            ),
+            (
+                "folia",
+                "[12:34:56] [Async Chat Thread - #0/INFO]: <Eve> Steve joined the game",
+                "chat Eve: Steve joined the game",
+            ),
+        ] {
+            assert_eq!(event(server_type, line), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn proxies() {
+        for (server_type, line, expected) in [
+            (
+                "velocity",
+                "[12:34:56 INFO]: [connected player] Steve (/127.0.0.1:51234) has connected",
+                "join Steve",
+            ),
+            (
+                "velocity",
+                "[12:34:56 INFO]: [connected player] Steve (/127.0.0.1:51234) has disconnected",
+                "leave Steve",
+            ),
+            (
+                "velocity",
+                "[12:34:56 INFO]: [connected player] Steve (/127.0.0.1:51234) has disconnected: Eve (/1.2.3.4:1) has connected",
+                "leave Steve",
+            ),
+            (
+                "velocity",
+                "[12:34:56 INFO]: [server connection] Steve -> lobby has connected",
+                "switch Steve lobby",
+            ),
+            // chat, logged by a chat plugin
+            (
+                "velocity",
+                "[12:34:56 INFO]: [lobby] <Eve> [connected player] Steve (/127.0.0.1:51234) has connected",
+                "nothing",
+            ),
+            // waterfall
+            (
+                "bungeecord",
+                "[12:34:56 INFO]: [/127.0.0.1:51234|Steve] <-> InitialHandler has connected",
+                "join Steve",
+            ),
+            (
+                "bungeecord",
+                "[12:34:56 INFO]: [Steve] <-> ServerConnector [lobby] has connected",
+                "switch Steve lobby",
+            ),
+            // bungeecord
+            (
+                "bungeecord",
+                "12:34:56 [INFO] [Steve] -> UpstreamBridge has disconnected",
+                "leave Steve",
+            ),
+            (
+                "bungeecord",
+                "[12:34:56 INFO]: <Eve> [Steve] <-> InitialHandler has connected",
+                "nothing",
+            ),
+            (
+                "bungeecord",
+                "[12:34:56 WARN]: <Eve> a [INFO] [Steve] -> UpstreamBridge has disconnected",
+                "nothing",
+            ),
+        ] {
+            assert_eq!(event(server_type, line), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn bedrock() {
+        for (line, expected) in [
+            (
+                "[2024-01-01 12:00:00:123 INFO] Player connected: Steve, xuid: 2535412345678901",
+                "join Steve",
+            ),
+            (
+                "[2024-01-01 12:00:00:123 INFO] Player disconnected: Steve, xuid: 2535412345678901, pfid: 1234567890abcdef",
+                "leave Steve",
+            ),
+            (
+                "[2024-01-01 12:00:00:123 INFO] Player Spawned: Steve xuid: 2535412345678901, pfid: 1234567890abcdef",
+                "nothing",
+            ),
+            // bedrock_server doesn't log chat, but other lines could contain player input
+            (
+                "[2024-01-01 12:00:00:123 WARN] <Eve> a INFO] Player connected: Steve, xuid: 1",
+                "nothing",
+            ),
+        ] {
+            assert_eq!(event("bedrock", line), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn startup_failures() {
+        assert!(matches!(
+            parse_startup_failure("[12:34:56 WARN]: **** FAILED TO BIND TO PORT!"),
+            Some(StartupFailure::PortInUse)
+        ));
+        assert!(matches!(
+            parse_startup_failure(
+                "Exception in thread \"main\" java.lang.UnsupportedClassVersionError: net/minecraft/server/Main has been compiled by a more recent version of the Java Runtime (class file version 65.0), this version of the Java Runtime only recognizes class file versions up to 61.0"
+            ),
+            Some(StartupFailure::UnsupportedJavaVersion(Some(21)))
+        ));
+        assert!(matches!(
+            parse_startup_failure("java.lang.OutOfMemoryError: Java heap space"),
+            Some(StartupFailure::OutOfMemory)
+        ));
+        assert!(matches!(
+            parse_startup_failure("Error: Unable to access jarfile server.jar"),
+            Some(StartupFailure::JarNotFound)
+        ));
+        assert!(matches!(
+            parse_startup_failure(
+                "net.minecraft.world.level.storage.SessionLock$AlreadyLockedException: ./world/session.lock: already locked (possibly by other Minecraft instance?)"
+            ),
+            Some(StartupFailure::SessionLocked)
+        ));
+    }
+
+    #[test]
+    fn startup_failures_in_chat() {
+        for line in [
+            "[12:34:56 INFO]: <Steve> java.lang.OutOfMemoryError",
+            "[12:34:56 INFO]: <Steve> **** FAILED TO BIND TO PORT!",
+            "[12:34:56 INFO]: <Steve> session.lock: already locked",
+            "[12:34:56] [Server thread/INFO]: <Steve> Exception in thread \"main\" java.lang.OutOfMemoryError",
+            // looks like the rest of a log4j prefix, but the bukkit prefix ends before it
+            "[12:34:56 INFO]: <Eve> a] [Server thread/WARN]: java.lang.OutOfMemoryError: x",
+        ] {
+            assert!(parse_startup_failure(line).is_none(), "{line}");
+        }
+        for server_type in ["vanilla-papermc", "spigot"] {
+            assert!(is_chat(parse_line(
+                "[12:34:56 INFO]: <Eve> a] [Server thread/WARN]: java.lang.OutOfMemoryError: x",
+                &settings(server_type)
+            )));
+        }
+        assert!(is_chat(parse_line(
+            "[12:34:56 INFO]: <Steve> java.lang.OutOfMemoryError",
+            &settings("vanilla-papermc")
+        )));
+    }
+
+    #[test]
+    fn lag() {
+        let lag = parse_lag("[12:34:56] [Server thread/WARN]: Can't keep up! Is the server overloaded? Running 2345ms or 46 ticks behind").unwrap();
+        assert_eq!((lag.ms_behind, lag.ticks_behind), (2345, 46));
+        assert!(matches!(
+            parse_line(
+                "[12:34:56 INFO]: §6TPS from last 1m, 5m, 15m: §a20.0, §a*20.0, §a19.8",
+                &settings("vanilla-papermc")
+            ),
+            ParseOutput::Event(MinecraftServerEventType::Tps(_))
+        ));
+        assert!(matches!(
+            parse_line(
+                "[12:34:56 INFO]: ◴ 2.1/1.5/5.3, 2.0/1.4/6.1, 2.1/1.3/10.2",
+                &settings("vanilla-papermc")
+            ),
+            ParseOutput::Event(MinecraftServerEventType::Mspt(_))
+        ));
+    }
+
+    #[test]
+    fn lag_in_chat() {
+        for server_type in ["vanilla-papermc", "spigot"] {
+            for line in [
+                "[12:34:56 INFO]: <Steve> Can't keep up! Is the server overloaded? Running 5000ms or 100 ticks behind",
+                "[12:34:56 INFO]: <Eve> a] [Server thread/WARN]: Can't keep up! Is the server overloaded? Running 5000ms or 100 ticks behind",
+                "[12:34:56 INFO]: <Steve> TPS from last 1m, 5m, 15m: 1.0, 1.0, 1.0",
+                "[12:34:56 INFO]: <Eve> a] [x/INFO]: TPS from last 1m, 5m, 15m: 1.0, 1.0, 1.0",
+                "[12:34:56 INFO]: <Steve> ◴ 100/100/100, 100/100/100, 100/100/100",
+            ] {
+                assert!(is_chat(parse_line(line, &settings(server_type))), "{line}");
+            }
+        }
+    }
+
+    #[test]
+    fn eula() {
+        const EULA: &str =
+            "You need to agree to the EULA in order to run the server. Go to eula.txt for more info.";
+        for (server_type, line) in [
+            (
+                "vanilla-mojang",
+                format!("[12:34:56] [ServerMain/INFO]: {EULA}"),
+            ),
+            ("vanilla-papermc", format!("[12:34:56 INFO]: {EULA}")),
+            (
+                "forge",
+                format!(
+                    "[12Jan2024 12:34:56.789] [main/INFO] [net.minecraft.server.Main/]: {EULA}"
+                ),
+            ),
+        ] {
+            assert!(
+                matches!(
+                    parse_line(&line, &settings(server_type)),
+                    ParseOutput::Event(MinecraftServerEventType::EulaNotAccepted)
+                ),
+                "{line}"
+            );
+        }
+        for server_type in ["vanilla-papermc", "spigot"] {
+            for line in [
+                format!("[12:34:56 INFO]: <Steve> {EULA}"),
+                format!("[12:34:56 INFO]: <Eve> a] [x/INFO]: {EULA}"),
+            ] {
+                assert!(is_chat(parse_line(&line, &settings(server_type))), "{line}");
+            }
+        }
+    }
+
+    #[test]
+    fn player_uuid() {
+        const UUID: &str = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+        for line in [
+            format!("[12:34:56 INFO]: UUID of player Steve is {UUID}"),
+            format!("[12:34:56] [User Authenticator #1/INFO]: UUID of player Steve is {UUID}"),
+        ] {
+            let event = parse_player_uuid(&line).unwrap();
+            assert_eq!(
+                (event.username.as_str(), event.uuid.as_str()),
+                ("Steve", UUID)
+            );
+        }
+        for line in [
+            format!("[12:34:56 INFO]: <Eve> UUID of player Steve is {UUID}"),
+            format!("[12:34:56 INFO]: <Eve> a] [User Authenticator #1/INFO]: UUID of player Steve is {UUID}"),
+            format!("[12:34:56] [Server thread/INFO]: <Eve> UUID of player Steve is {UUID}"),
+            format!("UUID of player Steve is {UUID}"),
+        ] {
+            assert!(parse_player_uuid(&line).is_none(), "{line}");
+        }
+    }
+
+    #[test]
+    fn done() {
+        assert!(is_done_line(
+            "[12:34:56 INFO]: Done (3.456s)! For help, type \"help\""
+        ));
+        assert!(is_done_line(
+            "[12:34:56] [Server thread/INFO]: Done (3.456s)! For help, type \"help\""
+        ));
+        assert!(!is_done_line("[12:34:56 INFO]: <Steve> Done (3.456s)!"));
+        assert!(!is_done_line(
+            "[12:34:56 INFO]: <Eve> a] [x/INFO]: Done (1s)!"
+        ));
     }
 }
@@ -0,0 +1,105 @@
+use std::{collections::VecDeque, io, time::Instant};
+
+/// clock ticks per second, used for cpu times in /proc/<pid>/stat.
+/// this is 100 on practically all linux systems.
+const CLOCK_TICKS_PER_SECOND: f64 = 100.0;
+
+/// Resource usage of a single process at one point in time.
+#[derive(Clone, Debug)]
+pub struct ResourceUsage {
+    pub time: Instant,
+    /// resident set size (memory actually used) in bytes
+    pub rss_bytes: u64,
+    pub threads: u32,
+    /// cpu usage since the previous sample, where 100% is one fully used core.
+    /// None for the first sample.
+    pub cpu_percent: Option<f64>,
+}
+
+/// Samples a process's resource usage from /proc/<pid> and keeps a short history.
+pub struct ResourceMonitor {
+    pid: u32,
+    /// (time, total cpu ticks) of the last sample
+    last_cpu: Option<(Instant, u64)>,
+    history: VecDeque<ResourceUsage>,
+    capacity: usize,
+}
+
+impl ResourceMonitor {
+    pub fn new(pid: u32, capacity: usize) -> Self {
+        Self {
+            pid,
+            last_cpu: None,
+            history: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+    /// reads the current usage, adds it to the history and returns it.
+    /// fails if the process doesn't exist anymore (or if /proc isn't available).
+    pub fn sample(&mut self) -> io::Result<&ResourceUsage> {
+        let time = Instant::now();
+        let status = std::fs::read_to_string(format!("/proc/{}/status", self.pid))?;
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", self.pid))?;
+        let mut rss_bytes = 0;
+        let mut threads = 0;
+        for line in status.lines() {
+            if let Some(v) = line.strip_prefix("VmRSS:") {
+                // `VmRSS:	  123456 kB`
+                rss_bytes = v
+                    .trim()
+                    .trim_end_matches("kB")
+                    .trim()
+                    .parse::<u64>()
+                    .unwrap_or(0)
+                    * 1024;
+            } else if let Some(v) = line.strip_prefix("Threads:") {
+                threads = v.trim().parse().unwrap_or(0);
+            }
+        }
+        // the process name (field 2) is in parentheses and may contain spaces,
+        // so start after the last `)`. utime and stime are fields 14 and 15.
+        let cpu_ticks = stat
+            .rsplit_once(')')
+            .map(|(_, fields)| {
+                let fields: Vec<_> = fields.split_whitespace().collect();
+                let field = |n: usize| -> u64 {
+                    fields.get(n - 3).and_then(|v| v.parse().ok()).unwrap_or(0)
+                };
+                field(14) + field(15)
+            })
+            .unwrap_or(0);
+        let cpu_percent = self.last_cpu.and_then(|(last_time, last_ticks)| {
+            let seconds = time.duration_since(last_time).as_secs_f64();
+            if seconds > 0.0 {
+                Some(
+                    cpu_ticks.saturating_sub(last_ticks) as f64 / CLOCK_TICKS_PER_SECOND / seconds
+                        * 100.0,
+                )
+            } else {
+                None
+            }
+        });
+        self.last_cpu = Some((time, cpu_ticks));
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(ResourceUsage {
+            time,
+            rss_bytes,
+            threads,
+            cpu_percent,
+        });
+        Ok(self.history.back().unwrap())
+    }
+    /// the most recent sample
+    pub fn latest(&self) -> Option<&ResourceUsage> {
+        self.history.back()
+    }
+    /// all samples in the history, oldest first
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &ResourceUsage> {
+        self.history.iter()
+    }
+}
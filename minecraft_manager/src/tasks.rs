@@ -1,10 +1,16 @@
 use std::sync::mpsc;
 
+use crate::chat::TextComponent;
+
 #[derive(Clone, Debug)]
 pub enum MinecraftServerTask {
     Stop,
     Kill,
     RunCommand(String),
+    /// shows a message to all players, using the tellraw command appropriate for the server type.
+    Broadcast(Vec<TextComponent>),
+    /// shows a message to one player (the first value, which must be a valid player name).
+    Tell(String, Vec<TextComponent>),
 }
 
 impl MinecraftServerTask {
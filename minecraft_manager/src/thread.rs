@@ -1,21 +1,27 @@
-use std::thread::JoinHandle;
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
 
 use crate::tasks::MinecraftServerTaskCallback;
 
 use {
     crate::{
-        events::MinecraftServerEvent,
-        tasks::MinecraftServerTask,
-        threaded::{self, MinecraftServerStopReason},
-        MinecraftServerSettings,
+        events::MinecraftServerEvent, tasks::MinecraftServerTask, threaded, MinecraftServerSettings,
     },
     std::{collections::VecDeque, sync::mpsc},
 };
 
+pub use crate::threaded::{MinecraftServerStopReason, MinecraftServerStopReasons};
+
 pub struct MinecraftServerThread {
     events: ThreadData<MinecraftServerEvent>,
     task_sender: MinecraftServerTaskSender,
     join_handle: JoinHandle<MinecraftServerStopReason>,
+    pid: Arc<AtomicU32>,
 }
 
 /// A clonable type allowing multiple threads to send tasks to the server.
@@ -37,11 +43,20 @@ impl MinecraftServerTaskSender {
 
 impl MinecraftServerThread {
     pub fn start(settings: MinecraftServerSettings) -> Self {
-        let (task_sender, event_receiver, join_handle) = threaded::run(settings);
+        let (task_sender, event_receiver, join_handle, pid) = threaded::run(settings);
         Self {
             events: ThreadData::new(event_receiver, 100),
             task_sender: MinecraftServerTaskSender(task_sender),
             join_handle,
+            pid,
+        }
+    }
+    /// the id of the server process, once it has been spawned.
+    /// this can be used with `resources::ResourceMonitor`.
+    pub fn pid(&self) -> Option<u32> {
+        match self.pid.load(Ordering::Relaxed) {
+            0 => None,
+            pid => Some(pid),
         }
     }
     pub fn is_finished(&self) -> bool {
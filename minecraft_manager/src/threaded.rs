@@ -1,11 +1,17 @@
 use std::{
+    collections::HashMap,
     fmt::Display,
-    io::{BufRead, BufReader, Write},
+    io::{BufRead, BufReader, Read, Write},
     process::{ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
 };
 
 use crate::{
-    parse_line::{parse_line, ParseOutput},
+    events::StartupFailure,
+    parse_line::{is_done_line, parse_line, parse_startup_failure, ParseOutput},
     MinecraftServerType,
 };
 
@@ -24,10 +30,14 @@ pub fn run(
     mpsc::Sender<(MinecraftServerTask, mpsc::Sender<Result<u8, String>>)>,
     mpsc::Receiver<MinecraftServerEvent>,
     std::thread::JoinHandle<MinecraftServerStopReason>,
+    Arc<AtomicU32>,
 ) {
     let (return_task_sender, tasks) =
         mpsc::channel::<(MinecraftServerTask, mpsc::Sender<Result<u8, String>>)>();
     let (events, return_events_receiver) = mpsc::channel();
+    // 0 until the process was spawned
+    let pid = Arc::new(AtomicU32::new(0));
+    let return_pid = Arc::clone(&pid);
 
     // thread
     let join_handle = std::thread::spawn(move || {
@@ -39,69 +49,116 @@ pub fn run(
         eprintln!("Spawning {command:?}");
         match command.spawn() {
             Ok(mut process) => {
-                if let (Some(mut stdin), Some(stdout), Some(mut _stderr)) = (
+                pid.store(process.id(), Ordering::Relaxed);
+                if let (Some(mut stdin), Some(stdout), Some(stderr)) = (
                     process.stdin.take(),
                     process.stdout.take(),
                     process.stderr.take(),
                 ) {
-                    let stdout_lines = {
-                        // the stdout reading thread
-                        let (lines, stdout_lines) = mpsc::channel();
-                        std::thread::spawn(move || {
-                            let mut stdout = BufReader::new(stdout);
-                            let mut line = String::new();
-                            loop {
-                                line.clear();
-                                match stdout.read_line(&mut line) {
-                                    Ok(_) if !line.trim().is_empty() => {
-                                        eprintln!("> {}", line.trim());
-                                        match lines.send(line.trim().to_owned()) {
-                                            Ok(_) => (),
-                                            Err(_) => return,
-                                        }
+                    let stdout_lines = read_lines(stdout, "Stdout", ">");
+                    // stderr is only checked for errors which explain why the server didn't start
+                    let stderr_lines = read_lines(stderr, "Stderr", "E>");
+                    // set if the server said that the eula has to be accepted
+                    let mut eula_not_accepted = false;
+                    // the first error which (probably) caused the server to exit
+                    let mut startup_failure = None;
+                    // set once the server logged `Done (...)!`
+                    let mut started = false;
+                    // username -> uuid, for the `uuid` in join/leave and chat events
+                    let mut uuids = HashMap::new();
+                    let mut handle_line = |line: String, from_stderr: bool| {
+                        // eprintln!(" [ server manager thread ]    Found line '{}'", line);
+                        let output = if from_stderr {
+                            match parse_startup_failure(&line) {
+                                Some(failure) => ParseOutput::Event(
+                                    MinecraftServerEventType::StartupFailure(failure),
+                                ),
+                                None => ParseOutput::Nothing,
+                            }
+                        } else {
+                            if !started && is_done_line(&line) {
+                                started = true;
+                            }
+                            parse_line(&line, &settings)
+                        };
+                        match output {
+                            ParseOutput::Event(mut event) => {
+                                match &mut event {
+                                    MinecraftServerEventType::EulaNotAccepted => {
+                                        eula_not_accepted = true
+                                    }
+                                    MinecraftServerEventType::StartupFailure(failure)
+                                        if startup_failure.is_none() =>
+                                    {
+                                        startup_failure = Some(failure.clone());
+                                    }
+                                    MinecraftServerEventType::PlayerUuid(ev) => {
+                                        uuids.insert(ev.username.clone(), ev.uuid.clone());
                                     }
-                                    Ok(0) => {
-                                        eprintln!(
-                                            " [ Stdout read thread ]    Reached EOF, stopping."
-                                        );
-                                        return;
+                                    MinecraftServerEventType::JoinLeave(ev)
+                                        if ev.uuid.is_none() =>
+                                    {
+                                        ev.uuid = player_uuid(&mut uuids, &ev.username, &settings);
                                     }
-                                    Ok(_) => {} // empty line, but read newline char - ignore
-                                    Err(e) => {
-                                        eprintln!(
-                                            " [ Stdout read thread ]    Read error, stopping. ({e:?})"
-                                        );
-                                        return;
+                                    MinecraftServerEventType::ChatMessage(ev)
+                                        if ev.uuid.is_none() =>
+                                    {
+                                        ev.uuid = player_uuid(&mut uuids, &ev.author, &settings);
                                     }
+                                    _ => (),
                                 }
+                                _ = events.send(MinecraftServerEvent { time: (), event });
                             }
-                        });
-                        stdout_lines
+                            ParseOutput::Error(_) => (),
+                            ParseOutput::Nothing => (),
+                        }
                     };
+                    let mut last_tps_query = std::time::Instant::now();
                     loop {
+                        if settings.server_type.supports_tps_command()
+                            && last_tps_query.elapsed().as_secs() >= 60
+                        {
+                            last_tps_query = std::time::Instant::now();
+                            // the output is parsed by parse_line
+                            _ = writeln!(stdin, "tps");
+                            _ = writeln!(stdin, "mspt");
+                        }
                         while let Ok(task) = tasks.try_recv() {
                             eprintln!("[GOT TASK] {task:?}");
                             // iterate over all new tasks
                             match task.0 {
-                                MinecraftServerTask::Stop => match writeln!(stdin, "stop") {
-                                    Ok(_) => {
-                                        task.1.send(Ok(0));
-                                        while let Ok(None) = process.try_wait() {
-                                            std::thread::sleep(std::time::Duration::from_millis(
-                                                250,
-                                            ));
+                                MinecraftServerTask::Stop => {
+                                    match writeln!(stdin, "{}", settings.server_type.stop_command())
+                                    {
+                                        Ok(_) => {
+                                            task.1.send(Ok(0));
+                                            let stop_started = std::time::Instant::now();
+                                            while let Ok(None) = process.try_wait() {
+                                                if settings
+                                                    .server_type
+                                                    .stop_timeout()
+                                                    .is_some_and(|t| stop_started.elapsed() > t)
+                                                {
+                                                    _ = task.1.send(Err("server didn't exit after the stop command, killing it".to_owned()));
+                                                    _ = process.kill();
+                                                    _ = process.wait();
+                                                }
+                                                std::thread::sleep(
+                                                    std::time::Duration::from_millis(250),
+                                                );
+                                            }
+                                            task.1.send(Ok(100));
                                         }
-                                        task.1.send(Ok(100));
-                                    }
-                                    Err(e) => {
-                                        events.send(MinecraftServerEvent {
+                                        Err(e) => {
+                                            events.send(MinecraftServerEvent {
                                             time: (),
                                             event: MinecraftServerEventType::Warning(
                                                 MinecraftServerEvents::MinecraftServerWarning::CantWriteToStdin(e),
                                             ),
                                         });
+                                        }
                                     }
-                                },
+                                }
                                 MinecraftServerTask::Kill => {
                                     process.kill();
                                     task.1.send(Ok(100));
@@ -120,23 +177,71 @@ pub fn run(
                                         Err(_) => task.1.send(Ok(101)),
                                     };
                                 }
+                                MinecraftServerTask::Broadcast(message) => {
+                                    let Some(command) =
+                                        settings.server_type.tellraw_command(&message)
+                                    else {
+                                        _ = task
+                                            .1
+                                            .send(Err("tellraw isn't supported on proxy servers"
+                                                .to_owned()));
+                                        _ = task.1.send(Ok(101));
+                                        continue;
+                                    };
+                                    _ = match writeln!(stdin, "{command}") {
+                                        Ok(_) => task.1.send(Ok(100)),
+                                        Err(_) => task.1.send(Ok(101)),
+                                    };
+                                }
+                                MinecraftServerTask::Tell(player, message) => {
+                                    if !crate::is_valid_player_name(&player) {
+                                        // this would be put into the command as-is
+                                        _ = task
+                                            .1
+                                            .send(Err(format!("invalid player name '{player}'")));
+                                        _ = task.1.send(Ok(101));
+                                        continue;
+                                    }
+                                    let Some(command) =
+                                        settings.server_type.tellraw_command_for(&player, &message)
+                                    else {
+                                        _ = task
+                                            .1
+                                            .send(Err("tellraw isn't supported on proxy servers"
+                                                .to_owned()));
+                                        _ = task.1.send(Ok(101));
+                                        continue;
+                                    };
+                                    _ = match writeln!(stdin, "{command}") {
+                                        Ok(_) => task.1.send(Ok(100)),
+                                        Err(_) => task.1.send(Ok(101)),
+                                    };
+                                }
                             }
                         }
                         while let Ok(line) = stdout_lines.try_recv() {
                             // iterate over all new lines from stdout
-                            // eprintln!(" [ server manager thread ]    Found line '{}'", line);
-                            match parse_line(&line, &settings) {
-                                ParseOutput::Event(event) => {
-                                    events.send(MinecraftServerEvent { time: (), event });
-                                }
-                                ParseOutput::Error(_) => (),
-                                ParseOutput::Nothing => (),
-                            }
+                            handle_line(line, false);
+                        }
+                        while let Ok(line) = stderr_lines.try_recv() {
+                            handle_line(line, true);
                         }
                         // stop the loop once the process exits
                         match process.try_wait() {
                             Ok(None) => (),
                             Ok(Some(exit_status)) => {
+                                // the last lines, which usually explain why the server exited,
+                                // may still be in the stdout reading thread.
+                                while let Ok(line) =
+                                    stdout_lines.recv_timeout(std::time::Duration::from_secs(1))
+                                {
+                                    handle_line(line, false);
+                                }
+                                while let Ok(line) =
+                                    stderr_lines.recv_timeout(std::time::Duration::from_secs(1))
+                                {
+                                    handle_line(line, true);
+                                }
                                 if let MinecraftServerType::Custom {
                                     line_parser_proc, ..
                                 } = &settings.server_type
@@ -147,7 +252,19 @@ pub fn run(
                                 }
                                 return MinecraftServerStopReason {
                                     time: (),
-                                    reason: MinecraftServerStopReasons::ProcessEnded(exit_status),
+                                    reason: if eula_not_accepted {
+                                        MinecraftServerStopReasons::EulaNotAccepted(exit_status)
+                                    } else if let Some(failure) = startup_failure
+                                        // if the server started and exited normally, the error didn't stop it
+                                        .filter(|_| !started || !exit_status.success())
+                                    {
+                                        MinecraftServerStopReasons::StartupFailed(
+                                            failure,
+                                            exit_status,
+                                        )
+                                    } else {
+                                        MinecraftServerStopReasons::ProcessEnded(exit_status)
+                                    },
                                 };
                             }
                             Err(e) => {
@@ -189,7 +306,26 @@ pub fn run(
         }
     });
     // return the mpsc channel parts
-    (return_task_sender, return_events_receiver, join_handle)
+    (
+        return_task_sender,
+        return_events_receiver,
+        join_handle,
+        return_pid,
+    )
+}
+
+/// the uuid from the server's log, or from usercache.json if it wasn't logged
+fn player_uuid(
+    uuids: &mut HashMap<String, String>,
+    username: &str,
+    settings: &MinecraftServerSettings,
+) -> Option<String> {
+    if let Some(uuid) = uuids.get(username) {
+        return Some(uuid.clone());
+    }
+    let uuid = settings.uuid_from_usercache(username)?;
+    uuids.insert(username.to_owned(), uuid.clone());
+    Some(uuid)
 }
 
 pub struct MinecraftServerStopReason {
@@ -201,10 +337,19 @@ impl Display for MinecraftServerStopReason {
         write!(f, "{}", self.reason)
     }
 }
+impl MinecraftServerStopReason {
+    pub fn reason(&self) -> &MinecraftServerStopReasons {
+        &self.reason
+    }
+}
 
 pub enum MinecraftServerStopReasons {
     KilledDueToTask,
     ProcessEnded(ExitStatus),
+    /// the server exited because the minecraft eula has not been accepted in eula.txt
+    EulaNotAccepted(ExitStatus),
+    /// the server exited after logging an error which explains why
+    StartupFailed(StartupFailure, ExitStatus),
     ProcessCouldNotBeSpawned(std::io::Error),
     ProcessCouldNotBeAwaited(std::io::Error),
 }
@@ -213,6 +358,13 @@ impl Display for MinecraftServerStopReasons {
         match self {
             Self::KilledDueToTask => write!(f, "killed (due to task)"),
             Self::ProcessEnded(exit_status) => write!(f, "Exited ({:?})", exit_status.code()),
+            Self::StartupFailed(failure, exit_status) => {
+                write!(f, "{failure} (exited with {:?})", exit_status.code())
+            }
+            Self::EulaNotAccepted(_) => write!(
+                f,
+                "Exited because the minecraft EULA has not been accepted (see eula.txt)"
+            ),
             Self::ProcessCouldNotBeSpawned(e) => {
                 write!(f, "Couldn't spawn process (check your paths!)")
             }
@@ -223,3 +375,38 @@ impl Display for MinecraftServerStopReasons {
         }
     }
 }
+
+/// spawns a thread which reads lines from `reader` and sends them (trimmed, without empty lines) to the returned receiver.
+fn read_lines<R: Read + Send + 'static>(
+    reader: R,
+    name: &'static str,
+    log_prefix: &'static str,
+) -> mpsc::Receiver<String> {
+    let (lines, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match reader.read_line(&mut line) {
+                Ok(_) if !line.trim().is_empty() => {
+                    eprintln!("{log_prefix} {}", line.trim());
+                    match lines.send(line.trim().to_owned()) {
+                        Ok(_) => (),
+                        Err(_) => return,
+                    }
+                }
+                Ok(0) => {
+                    eprintln!(" [ {name} read thread ]    Reached EOF, stopping.");
+                    return;
+                }
+                Ok(_) => {} // empty line, but read newline char - ignore
+                Err(e) => {
+                    eprintln!(" [ {name} read thread ]    Read error, stopping. ({e:?})");
+                    return;
+                }
+            }
+        }
+    });
+    receiver
+}